@@ -0,0 +1,93 @@
+//! Compares the scheduler's central dispatcher against the model it replaced, where every job
+//! slept in its own task until its next fire and ran its callback inline. Both run on a paused
+//! tokio clock so only the scheduling overhead is measured.
+//!
+//! Run with `cargo bench --bench dispatch`.
+
+use chrono::Utc;
+use scheduler::clock::{Clock, TokioClock};
+use scheduler::job::Job;
+use scheduler::scheduler::Scheduler;
+use scheduler::trigger::{Interval, Trigger};
+use scheduler::triggerSet;
+use serde_json::Value;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+
+const INTERVAL: Duration = Duration::from_secs(10);
+const WINDOW: Duration = Duration::from_secs(60);
+
+static RUNS: AtomicUsize = AtomicUsize::new(0);
+
+fn count(_context: &Value) {
+    RUNS.fetch_add(1, Ordering::Relaxed);
+}
+
+fn job(index: usize) -> Job {
+    Job::new(
+        format!("job-{index}"),
+        Some(count),
+        Value::Null,
+        triggerSet![Interval::new(INTERVAL)],
+    )
+}
+
+// a plain loop rather than `Job::run_with_clock`, which now runs a dispatcher of its own
+async fn per_task(jobs: usize) {
+    let clock = TokioClock::new(Utc::now());
+    let mut tasks = JoinSet::new();
+    for _ in 0..jobs {
+        let trigger = Interval::new(INTERVAL);
+        tasks.spawn(async move {
+            let mut after = clock.now();
+            while let Some(next) = trigger.next_after(after) {
+                clock.sleep_until(next).await;
+                count(&Value::Null);
+                after = next;
+            }
+        });
+    }
+    tokio::time::sleep(WINDOW).await;
+    tasks.shutdown().await;
+}
+
+async fn dispatcher(jobs: usize) {
+    let mut scheduler = Scheduler::new().with_clock(TokioClock::new(Utc::now()));
+    for index in 0..jobs {
+        scheduler.add_job(job(index)).unwrap();
+    }
+    let handle = scheduler.handle();
+    let scheduler = tokio::spawn(scheduler.run());
+    tokio::time::sleep(WINDOW).await;
+    handle.shutdown(Duration::from_secs(1)).await.unwrap();
+    scheduler.await.unwrap();
+}
+
+fn measure<F, Fut>(model: &str, jobs: usize, run: F)
+where
+    F: FnOnce(usize) -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .start_paused(true)
+        .build()
+        .unwrap();
+    RUNS.store(0, Ordering::Relaxed);
+    let start = Instant::now();
+    runtime.block_on(run(jobs));
+    let elapsed = start.elapsed();
+    let runs = RUNS.load(Ordering::Relaxed);
+    println!(
+        "{model:>10} {jobs:>6} jobs {runs:>7} runs {elapsed:>12.2?} {:>8.2?}/run",
+        elapsed / runs.max(1) as u32
+    );
+}
+
+fn main() {
+    for jobs in [100, 1_000, 10_000] {
+        measure("per-task", jobs, per_task);
+        measure("dispatcher", jobs, dispatcher);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::job::BoxFuture;
+
+use chrono::{DateTime, Utc};
+use std::fmt::Debug;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Source of the current time for triggers and the scheduler.
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Completes once [`Clock::now`] has reached `deadline`.
+    fn sleep_until(&self, deadline: DateTime<Utc>) -> BoxFuture<()> {
+        let duration = (deadline - self.now()).to_std().unwrap_or_default();
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// The wall clock.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock that is stuck at one instant. Sleeps past it never complete.
+#[derive(Clone, Copy, Debug)]
+pub struct FixedClock(DateTime<Utc>);
+
+impl FixedClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self(now)
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+
+    fn sleep_until(&self, deadline: DateTime<Utc>) -> BoxFuture<()> {
+        match deadline <= self.0 {
+            true => Box::pin(std::future::ready(())),
+            false => Box::pin(std::future::pending()),
+        }
+    }
+}
+
+/// A clock that only moves when told to. Clones share the same time.
+#[derive(Clone, Debug)]
+pub struct ManualClock {
+    now: watch::Sender<DateTime<Utc>>,
+}
+
+impl ManualClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now: watch::Sender::new(now),
+        }
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        self.now.send_replace(now);
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.now.send_modify(|now| *now += duration);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.borrow()
+    }
+
+    fn sleep_until(&self, deadline: DateTime<Utc>) -> BoxFuture<()> {
+        let mut now = self.now.subscribe();
+        Box::pin(async move {
+            // a clock that was dropped never gets there
+            if now.wait_for(|now| *now >= deadline).await.is_err() {
+                std::future::pending::<()>().await;
+            }
+        })
+    }
+}
+
+/// Starts at a given instant and follows tokio's timer from there, so schedules can be fast
+/// forwarded with `tokio::time::pause` and `tokio::time::advance`.
+#[derive(Clone, Copy, Debug)]
+pub struct TokioClock {
+    start: DateTime<Utc>,
+    started: tokio::time::Instant,
+}
+
+impl TokioClock {
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            start,
+            started: tokio::time::Instant::now(),
+        }
+    }
+}
+
+impl Clock for TokioClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.start + self.started.elapsed()
+    }
+}
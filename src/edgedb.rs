@@ -0,0 +1,162 @@
+use crate::error::StoreError;
+use crate::job::{Job, RunHistory};
+use crate::store::{JobStore, Result};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Schema expected by [`EdgeDbStore`], to be added to the project's `.esdl` files.
+pub const SCHEMA: &str = r#"
+module scheduler {
+    type Job {
+        required name: str {
+            constraint exclusive;
+        };
+        required definition: json;
+        last_run: datetime;
+        paused: bool;
+        history: json;
+    }
+}
+"#;
+
+#[derive(Deserialize)]
+struct StoredJob {
+    definition: Value,
+    last_run: Option<DateTime<Utc>>,
+    paused: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct StoredHistory {
+    name: String,
+    history: RunHistory,
+}
+
+impl From<edgedb_tokio::Error> for StoreError {
+    fn from(error: edgedb_tokio::Error) -> Self {
+        Self::Backend(error.to_string())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EdgeDbStore {
+    client: edgedb_tokio::Client,
+}
+
+impl EdgeDbStore {
+    pub fn new(client: edgedb_tokio::Client) -> Self {
+        Self { client }
+    }
+
+    /// Connects using the environment or project configuration, like `edgedb_tokio::create_client`.
+    pub async fn connect() -> Result<Self> {
+        Ok(Self::new(edgedb_tokio::create_client().await?))
+    }
+}
+
+#[async_trait]
+impl JobStore for EdgeDbStore {
+    async fn load_all(&self) -> Result<Vec<Job>> {
+        let stored = self
+            .client
+            .query_json(
+                "select scheduler::Job { definition, last_run, paused }",
+                &(),
+            )
+            .await?;
+        serde_json::from_str::<Vec<StoredJob>>(&stored)?
+            .into_iter()
+            .map(|stored| {
+                let mut job: Job = serde_json::from_value(stored.definition)?;
+                if stored.last_run.is_some() {
+                    job.set_last_run(stored.last_run);
+                }
+                if let Some(paused) = stored.paused {
+                    job.set_paused(paused);
+                }
+                Ok(job)
+            })
+            .collect()
+    }
+
+    async fn upsert(&self, job: &Job) -> Result<()> {
+        let definition = serde_json::to_string(job)?;
+        self.client
+            .execute(
+                "insert scheduler::Job {
+                    name := <str>$0,
+                    definition := <json><str>$1,
+                    paused := <bool>$2,
+                }
+                unless conflict on .name
+                else (update scheduler::Job set {
+                    definition := <json><str>$1,
+                    paused := <bool>$2,
+                })",
+                &(job.name.clone(), definition, job.is_paused()),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn delete(&self, name: &str) -> Result<()> {
+        self.client
+            .execute(
+                "delete scheduler::Job filter .name = <str>$0",
+                &(name.to_string(),),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn record_last_run(&self, name: &str, at: DateTime<Utc>) -> Result<()> {
+        self.client
+            .execute(
+                "update scheduler::Job filter .name = <str>$0
+                set { last_run := max({.last_run, <datetime><str>$1}) }",
+                &(name.to_string(), at.to_rfc3339()),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn record_paused(&self, name: &str, paused: bool) -> Result<()> {
+        self.client
+            .execute(
+                "update scheduler::Job filter .name = <str>$0
+                set { paused := <bool>$1 }",
+                &(name.to_string(), paused),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn record_history(&self, name: &str, history: &RunHistory) -> Result<()> {
+        self.client
+            .execute(
+                "update scheduler::Job filter .name = <str>$0
+                set { history := <json><str>$1 }",
+                &(name.to_string(), serde_json::to_string(history)?),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn load_histories(&self) -> Result<BTreeMap<String, RunHistory>> {
+        let stored = self
+            .client
+            .query_json(
+                "select scheduler::Job { name, history } filter exists .history",
+                &(),
+            )
+            .await?;
+        Ok(serde_json::from_str::<Vec<StoredHistory>>(&stored)?
+            .into_iter()
+            .map(|stored| (stored.name, stored.history))
+            .collect())
+    }
+}
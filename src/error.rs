@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum JobError {
+    /// None of the job's triggers will fire again.
+    NoMoreRuns,
+    /// The callback returned an error.
+    Failed(String),
+    /// The callback panicked, carrying the panic message.
+    Panicked(String),
+    /// The callback did not finish within the allowed time.
+    TimedOut(Duration),
+    /// The run was cancelled before it could finish.
+    Cancelled,
+}
+
+impl JobError {
+    pub fn failed(reason: impl fmt::Display) -> Self {
+        Self::Failed(reason.to_string())
+    }
+}
+
+impl fmt::Display for JobError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JobError::NoMoreRuns => write!(f, "no more runs"),
+            JobError::Failed(reason) => write!(f, "callback failed: {}", reason),
+            JobError::Panicked(message) => write!(f, "callback panicked: {}", message),
+            JobError::TimedOut(timeout) => write!(f, "timed out after {:?}", timeout),
+            JobError::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for JobError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchedulerError {
+    /// No job with this name is registered.
+    UnknownJob(String),
+    /// A job with this name is already registered.
+    DuplicateJob(String),
+    /// The scheduler is no longer running.
+    Stopped,
+    /// The job store rejected a change.
+    Store(String),
+    /// The job refers to a callback that is not in the registry.
+    UnknownCallback(String),
+    /// The job would end up depending on itself, through the jobs listed in order.
+    DependencyCycle(Vec<String>),
+    /// A trigger of the job refers to a calendar the scheduler doesn't know.
+    UnknownCalendar(String),
+    /// A trigger of the job brings its own calendar, which differs from the scheduler's calendar
+    /// of the same name.
+    ConflictingCalendar(String),
+}
+
+impl fmt::Display for SchedulerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SchedulerError::UnknownJob(name) => write!(f, "unknown job \"{}\"", name),
+            SchedulerError::DuplicateJob(name) => write!(f, "job \"{}\" already exists", name),
+            SchedulerError::Stopped => write!(f, "scheduler is not running"),
+            SchedulerError::Store(error) => write!(f, "{}", error),
+            SchedulerError::UnknownCallback(name) => write!(f, "unknown callback \"{}\"", name),
+            SchedulerError::DependencyCycle(jobs) => {
+                write!(f, "dependency cycle: {}", jobs.join(" -> "))
+            }
+            SchedulerError::UnknownCalendar(name) => write!(f, "unknown calendar \"{}\"", name),
+            SchedulerError::ConflictingCalendar(name) => {
+                write!(f, "calendar \"{}\" differs from the scheduler's", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SchedulerError {}
+
+#[derive(Debug)]
+pub enum StoreError {
+    Io(std::io::Error),
+    Serialization(serde_json::Error),
+    /// Error reported by a database backend.
+    Backend(String),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StoreError::Io(error) => write!(f, "job store io error: {}", error),
+            StoreError::Serialization(error) => {
+                write!(f, "job store serialization error: {}", error)
+            }
+            StoreError::Backend(error) => write!(f, "job store backend error: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<std::io::Error> for StoreError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for StoreError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Serialization(error)
+    }
+}
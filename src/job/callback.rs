@@ -0,0 +1,141 @@
+use crate::error::JobError;
+use crate::job::Result;
+
+use serde_json::Value;
+use std::any::Any;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::task::{AbortHandle, JoinError};
+use tokio_util::sync::CancellationToken;
+
+pub type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+pub type AsyncCallback =
+    Arc<dyn Fn(Value, CancellationToken) -> BoxFuture<Result<Value>> + Send + Sync>;
+
+#[derive(Clone)]
+pub enum Callback {
+    /// Plain function, run on tokio's blocking thread pool.
+    Blocking(fn(context: &Value)),
+    /// Plain function returning a result, run on tokio's blocking thread pool.
+    Fallible(fn(context: &Value) -> Result<Value>),
+    /// Closure returning a future, run on its own task. The token is cancelled when the run
+    /// times out, is replaced or the scheduler shuts down.
+    Async(AsyncCallback),
+}
+
+impl Callback {
+    pub fn from_async<F, Fut>(callback: F) -> Self
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Value>> + Send + 'static,
+    {
+        Self::Async(Arc::new(move |context, _| Box::pin(callback(context))))
+    }
+
+    pub fn from_cancellable<F, Fut>(callback: F) -> Self
+    where
+        F: Fn(Value, CancellationToken) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Value>> + Send + 'static,
+    {
+        Self::Async(Arc::new(move |context, stop| {
+            Box::pin(callback(context, stop))
+        }))
+    }
+
+    pub async fn call(&self, context: &Value, stop: CancellationToken) -> Result<Value> {
+        let context = context.clone();
+        let result = match self {
+            Callback::Blocking(callback) => {
+                let callback = *callback;
+                tokio::task::spawn_blocking(move || {
+                    callback(&context);
+                    Ok(Value::Null)
+                })
+                .await
+            }
+            Callback::Fallible(callback) => {
+                let callback = *callback;
+                tokio::task::spawn_blocking(move || callback(&context)).await
+            }
+            Callback::Async(callback) => {
+                let task = tokio::spawn(callback(context, stop));
+                // abort the callback if the run itself is dropped
+                let _guard = AbortOnDrop(task.abort_handle());
+                task.await
+            }
+        };
+        result.unwrap_or_else(|error| Err(join_error(error)))
+    }
+}
+
+struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+fn join_error(error: JoinError) -> JobError {
+    match error.try_into_panic() {
+        Ok(payload) => JobError::Panicked(panic_message(payload)),
+        Err(_) => JobError::Cancelled,
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&'static str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "unknown panic".to_string(),
+        },
+    }
+}
+
+impl From<fn(&Value)> for Callback {
+    fn from(callback: fn(&Value)) -> Self {
+        Self::Blocking(callback)
+    }
+}
+
+impl From<fn(&Value) -> Result<Value>> for Callback {
+    fn from(callback: fn(&Value) -> Result<Value>) -> Self {
+        Self::Fallible(callback)
+    }
+}
+
+impl PartialEq for Callback {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Callback::Blocking(a), Callback::Blocking(b)) => std::ptr::fn_addr_eq(*a, *b),
+            (Callback::Fallible(a), Callback::Fallible(b)) => std::ptr::fn_addr_eq(*a, *b),
+            (Callback::Async(a), Callback::Async(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Callback {}
+
+impl fmt::Debug for Callback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Callback::Blocking(callback) => f
+                .debug_tuple("Blocking")
+                .field(&(*callback as *const ()))
+                .finish(),
+            Callback::Fallible(callback) => f
+                .debug_tuple("Fallible")
+                .field(&(*callback as *const ()))
+                .finish(),
+            Callback::Async(callback) => f
+                .debug_tuple("Async")
+                .field(&Arc::as_ptr(callback))
+                .finish(),
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use super::RunRecord;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// The latest runs of a job, oldest first, along with counters over its whole lifetime.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RunHistory {
+    capacity: usize,
+    records: VecDeque<RunRecord>,
+    runs: u64,
+    failures: u64,
+    last_success: Option<DateTime<Utc>>,
+}
+
+/// Aggregates over a job's [`RunHistory`]. Durations cover the retained runs only.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct JobStats {
+    pub runs: u64,
+    pub failures: u64,
+    pub p50: Option<Duration>,
+    pub p99: Option<Duration>,
+    /// When the latest successful run started.
+    pub last_success: Option<DateTime<Utc>>,
+}
+
+impl RunHistory {
+    /// Keeps up to `capacity` records, dropping the oldest beyond that.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            records: VecDeque::with_capacity(capacity),
+            runs: 0,
+            failures: 0,
+            last_success: None,
+        }
+    }
+
+    pub fn push(&mut self, record: RunRecord) {
+        self.runs += 1;
+        match record.is_success() {
+            true => self.last_success = self.last_success.max(Some(record.started)),
+            false => self.failures += 1,
+        }
+        if self.capacity == 0 {
+            return;
+        }
+        if self.records.len() == self.capacity {
+            self.records.pop_front();
+        }
+        self.records.push_back(record);
+    }
+
+    /// Changes the capacity, dropping the oldest records if there are too many.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        let excess = self.records.len().saturating_sub(capacity);
+        self.records.drain(..excess);
+    }
+
+    pub fn records(&self) -> impl Iterator<Item = &RunRecord> {
+        self.records.iter()
+    }
+
+    pub fn stats(&self) -> JobStats {
+        let mut durations: Vec<_> = self.records.iter().map(|run| run.duration).collect();
+        durations.sort();
+        // nearest rank
+        let percentile = |p: f64| {
+            let rank = (p * durations.len() as f64).ceil() as usize;
+            durations.get(rank.max(1) - 1).copied()
+        };
+        JobStats {
+            runs: self.runs,
+            failures: self.failures,
+            p50: percentile(0.5),
+            p99: percentile(0.99),
+            last_success: self.last_success,
+        }
+    }
+}
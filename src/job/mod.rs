@@ -1,97 +1,422 @@
-use crate::trigger::{NowUtc, TriggerSet};
+pub mod callback;
+pub mod history;
+pub mod policy;
+pub mod registry;
+pub mod run;
 
-use chrono::{DateTime, Duration as ChronoDuration, Utc};
-use itertools::Itertools;
+use crate::clock::{Clock, SystemClock};
+use crate::error::JobError;
+use crate::scheduler::dispatcher::{Dispatcher, DEFAULT_WORKERS};
+use crate::trigger::{Trigger, TriggerSet};
+
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::fmt;
+use std::borrow::Cow;
 use std::fmt::Debug;
-use tokio::task::JoinSet;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::task::{AbortHandle, JoinSet};
 use tokio::time::sleep;
-use tracing::debug;
+use tokio_util::sync::CancellationToken;
+use tracing::{info_span, warn, Instrument};
 
-pub type Result<T> = std::result::Result<T, NoMoreRunsError>;
+// upper bound on missed fires replayed by `MisfirePolicy::RunAll`, later ones are skipped
+const MAX_MISSED_RUNS: usize = 1000;
 
-#[derive(Debug, Clone)]
-pub struct NoMoreRunsError;
+pub type Result<T> = std::result::Result<T, JobError>;
 
-impl fmt::Display for NoMoreRunsError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "no more runs")
-    }
-}
+type TriggerFire<'a> = (&'a dyn Trigger, DateTime<Utc>);
+
+pub use self::callback::{BoxFuture, Callback};
+pub use self::history::{JobStats, RunHistory};
+pub use self::policy::{MisfirePolicy, OverlapPolicy, RetryPolicy};
+pub use self::registry::CallbackRegistry;
+pub use self::run::RunRecord;
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
 pub struct Job {
     pub name: String,
     #[serde(skip)]
-    callback: Option<fn(context: &Value)>,
+    callback: Option<Callback>,
+    #[serde(rename = "callback", default, skip_serializing_if = "Option::is_none")]
+    callback_name: Option<String>,
     callback_context: Value,
     triggers: TriggerSet,
+    #[serde(default, skip_serializing_if = "MisfirePolicy::is_skip")]
+    misfire_policy: MisfirePolicy,
+    #[serde(default, skip_serializing_if = "OverlapPolicy::is_skip")]
+    overlap_policy: OverlapPolicy,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timeout: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    retry_policy: Option<RetryPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    concurrency_group: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_run: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    paused: bool,
 }
 
-#[cfg(not(test))]
-impl NowUtc for Job {}
-
 impl Job {
-    pub fn new(
+    fn with_callback(
         name: String,
-        callback: Option<fn(context: &Value)>,
+        callback: Option<Callback>,
+        callback_name: Option<String>,
         callback_context: Value,
         triggers: TriggerSet,
     ) -> Self {
         Self {
             name,
             callback,
+            callback_name,
             callback_context,
             triggers,
+            misfire_policy: MisfirePolicy::default(),
+            overlap_policy: OverlapPolicy::default(),
+            timeout: None,
+            retry_policy: None,
+            concurrency_group: None,
+            last_run: None,
+            paused: false,
         }
     }
 
-    pub fn next_run(triggers: &TriggerSet) -> Option<DateTime<Utc>> {
-        triggers
-            .iter()
-            .filter_map(|t| {
-                let next_run = t.next_runs(1);
-                next_run.map(|next_run| next_run[0].to_owned())
-            })
-            .sorted()
-            .take(1)
-            .next()
+    pub fn new(
+        name: String,
+        callback: Option<fn(context: &Value)>,
+        callback_context: Value,
+        triggers: TriggerSet,
+    ) -> Self {
+        Self::with_callback(
+            name,
+            callback.map(Callback::from),
+            None,
+            callback_context,
+            triggers,
+        )
+    }
+
+    pub fn new_async<F, Fut>(
+        name: String,
+        callback: F,
+        callback_context: Value,
+        triggers: TriggerSet,
+    ) -> Self
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Value>> + Send + 'static,
+    {
+        Self::with_callback(
+            name,
+            Some(Callback::from_async(callback)),
+            None,
+            callback_context,
+            triggers,
+        )
     }
 
-    fn start_task(
-        tasks: &mut JoinSet<Result<()>>,
+    pub fn new_fallible(
+        name: String,
+        callback: fn(context: &Value) -> Result<Value>,
+        callback_context: Value,
+        triggers: TriggerSet,
+    ) -> Self {
+        Self::with_callback(
+            name,
+            Some(Callback::Fallible(callback)),
+            None,
+            callback_context,
+            triggers,
+        )
+    }
+
+    /// Like [`Job::new_async`], but the callback also gets a token that is cancelled when the run
+    /// should wrap up early, e.g. because it timed out or the scheduler is shutting down.
+    pub fn new_cancellable<F, Fut>(
         name: String,
+        callback: F,
+        callback_context: Value,
         triggers: TriggerSet,
-        callback: fn(context: &Value),
+    ) -> Self
+    where
+        F: Fn(Value, CancellationToken) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Value>> + Send + 'static,
+    {
+        Self::with_callback(
+            name,
+            Some(Callback::from_cancellable(callback)),
+            None,
+            callback_context,
+            triggers,
+        )
+    }
+
+    /// Creates a job whose callback is looked up by name in a [`CallbackRegistry`] when the job
+    /// is added to a scheduler or deserialized through the registry.
+    pub fn new_registered(
+        name: String,
+        callback_name: &str,
         callback_context: Value,
-    ) {
-        tasks.spawn(async move {
-            loop {
-                let next_run = Job::next_run(&triggers).ok_or(NoMoreRunsError)?;
-                let sleep_time = next_run - Self::now_utc();
-                debug!(name, at = { next_run.to_rfc3339() }, "in" = %sleep_time, "next run");
-                let sleep_time = sleep_time
-                    .clamp(ChronoDuration::zero(), ChronoDuration::max_value())
-                    .to_std()
-                    .unwrap();
-
-                sleep(sleep_time).await;
-
-                debug!(name, "triggered");
-                callback(&callback_context);
+        triggers: TriggerSet,
+    ) -> Self {
+        Self::with_callback(
+            name,
+            None,
+            Some(callback_name.to_string()),
+            callback_context,
+            triggers,
+        )
+    }
+
+    pub fn with_misfire_policy(mut self, misfire_policy: MisfirePolicy) -> Self {
+        self.misfire_policy = misfire_policy;
+        self
+    }
+
+    pub fn with_overlap_policy(mut self, overlap_policy: OverlapPolicy) -> Self {
+        self.overlap_policy = overlap_policy;
+        self
+    }
+
+    /// Gives up on runs that take longer than `timeout`. Async callbacks are aborted, blocking
+    /// ones keep running in the background but the run is reported as timed out.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Makes runs wait for a permit of the scheduler's concurrency group `group`, see
+    /// [`Scheduler::with_concurrency_group`](crate::scheduler::Scheduler::with_concurrency_group).
+    pub fn with_concurrency_group(mut self, group: &str) -> Self {
+        self.concurrency_group = Some(group.to_string());
+        self
+    }
+
+    pub fn callback_name(&self) -> Option<&str> {
+        self.callback_name.as_deref()
+    }
+
+    pub fn is_resolved(&self) -> bool {
+        self.callback_name.is_none() || self.callback.is_some()
+    }
+
+    pub fn triggers(&self) -> &TriggerSet {
+        &self.triggers
+    }
+
+    pub fn misfire_policy(&self) -> MisfirePolicy {
+        self.misfire_policy
+    }
+
+    pub fn overlap_policy(&self) -> OverlapPolicy {
+        self.overlap_policy
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    pub fn retry_policy(&self) -> Option<RetryPolicy> {
+        self.retry_policy
+    }
+
+    pub fn concurrency_group(&self) -> Option<&str> {
+        self.concurrency_group.as_deref()
+    }
+
+    /// The last fire that was handled, or when the job was first scheduled if it never fired.
+    pub fn last_run(&self) -> Option<DateTime<Utc>> {
+        self.last_run
+    }
+
+    pub(crate) fn set_last_run(&mut self, last_run: Option<DateTime<Utc>>) {
+        self.last_run = last_run;
+    }
+
+    /// Whether the job was paused through the scheduler's handle when it was stored.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub(crate) fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn next_run(triggers: &TriggerSet, clock: &dyn Clock) -> Option<DateTime<Utc>> {
+        triggers.next_after(clock.now())
+    }
+
+    // fires from `first_missed` up to `now` that the misfire policy wants run, with the trigger
+    // behind each, and the fire to continue the schedule from
+    pub(crate) fn misfired<'a>(
+        &'a self,
+        first_missed: TriggerFire<'a>,
+        now: DateTime<Utc>,
+    ) -> (Vec<TriggerFire<'a>>, DateTime<Utc>) {
+        let name = &self.name;
+        let policy = self.misfire_policy;
+        let mut missed = std::iter::successors(Some(first_missed), |(_, after)| {
+            self.triggers.next_fire_after(*after)
+        })
+        .take_while(|(_, run)| *run < now);
+
+        if policy == MisfirePolicy::RunAll {
+            let runs: Vec<_> = missed.by_ref().take(MAX_MISSED_RUNS).collect();
+            let (skipped, latest) = missed.fold((0, runs[runs.len() - 1]), |(count, _), run| {
+                (count + 1, run)
+            });
+            match skipped {
+                0 => warn!(name, missed = runs.len(), ?policy, "replaying missed runs"),
+                _ => warn!(
+                    name,
+                    missed = runs.len() + skipped,
+                    replayed = runs.len(),
+                    ?policy,
+                    "replaying missed runs up to the limit, skipping the rest"
+                ),
+            }
+            return (runs, latest.1);
+        }
+
+        let (count, latest) = missed.fold((0, first_missed), |(count, _), run| (count + 1, run));
+        let run_latest = match policy {
+            MisfirePolicy::RunOnce => true,
+            MisfirePolicy::Grace(grace) => {
+                (now - latest.1).to_std().is_ok_and(|late| late <= grace)
+            }
+            _ => false,
+        };
+        if run_latest {
+            warn!(name, missed = count, ?policy, "running latest missed run");
+            (vec![latest], latest.1)
+        } else {
+            warn!(name, missed = count, ?policy, "skipping missed runs");
+            (Vec::new(), latest.1)
+        }
+    }
+
+    /// Runs the callback once, with `payload` merged into the context. `stop` is handed to
+    /// cancellable callbacks and cancelled once the run is over; `cancel` abandons the run
+    /// outright with `JobError::Cancelled`.
+    pub(crate) async fn execute(
+        &self,
+        clock: &dyn Clock,
+        scheduled: DateTime<Utc>,
+        payload: Option<&Value>,
+        stop: CancellationToken,
+        cancel: &CancellationToken,
+    ) -> RunRecord {
+        let started = clock.now();
+        let start = Instant::now();
+        let context = match payload {
+            Some(payload) => Cow::Owned(merge_context(&self.callback_context, payload)),
+            None => Cow::Borrowed(&self.callback_context),
+        };
+        let call = async {
+            match (&self.callback, &self.callback_name) {
+                (Some(callback), _) => callback.call(&context, stop.clone()).await,
+                (None, Some(callback_name)) => Err(JobError::failed(format!(
+                    "callback \"{}\" was never resolved",
+                    callback_name
+                ))),
+                (None, None) => Ok(Value::Null),
+            }
+        };
+        let timeout = async {
+            match self.timeout {
+                Some(timeout) => sleep(timeout).await,
+                None => std::future::pending().await,
+            }
+        };
+        let outcome = tokio::select! {
+            outcome = call => outcome,
+            _ = cancel.cancelled() => Err(JobError::Cancelled),
+            _ = timeout => Err(JobError::TimedOut(self.timeout.unwrap_or_default())),
+        };
+        // blocking callbacks outlive a timeout, let them notice
+        stop.cancel();
+        RunRecord {
+            job: self.name.clone(),
+            scheduled,
+            started,
+            duration: start.elapsed(),
+            outcome,
+            attempt: 1,
+            manual: false,
+            trigger: None,
+        }
+    }
+
+    /// Runs the job's schedule from `since`, treating fires before `clock`'s current time as
+    /// missed.
+    pub(crate) fn start_task(
+        tasks: &mut JoinSet<Result<()>>,
+        job: Arc<Self>,
+        clock: Arc<dyn Clock>,
+        since: Option<DateTime<Utc>>,
+        results: Option<UnboundedSender<RunRecord>>,
+        shutdown: CancellationToken,
+    ) -> AbortHandle {
+        let span = info_span!("job", name = job.name);
+        let mut dispatcher = Dispatcher::new(clock, DEFAULT_WORKERS, results);
+        dispatcher.insert(job, since);
+        tasks.spawn(
+            async move {
+                while !dispatcher.is_idle() {
+                    tokio::select! {
+                        biased;
+                        _ = shutdown.cancelled() => {
+                            dispatcher.shutdown();
+                            while !dispatcher.is_idle() {
+                                dispatcher.step().await;
+                            }
+                            return Ok(());
+                        }
+                        _ = dispatcher.step() => {}
+                    }
+                }
+                Err(JobError::NoMoreRuns)
             }
-        });
+            .instrument(span),
+        )
     }
 
     pub fn run(job: Self, tasks: &mut JoinSet<Result<()>>) {
+        Job::run_with_clock(job, Arc::new(SystemClock), tasks);
+    }
+
+    pub fn run_with_clock(job: Self, clock: Arc<dyn Clock>, tasks: &mut JoinSet<Result<()>>) {
+        let since = job.last_run;
         Job::start_task(
             tasks,
-            job.name,
-            job.triggers,
-            job.callback.unwrap_or(|_| {}),
-            job.callback_context,
+            Arc::new(job),
+            clock,
+            since,
+            None,
+            CancellationToken::new(),
         );
     }
 }
+
+// fields of an object payload override those of an object context, other payloads replace the
+// context unless they are null
+fn merge_context(context: &Value, payload: &Value) -> Value {
+    match (context, payload) {
+        (Value::Object(context), Value::Object(payload)) => {
+            let mut merged = context.clone();
+            merged.extend(payload.clone());
+            Value::Object(merged)
+        }
+        (context, Value::Null) => context.clone(),
+        (_, payload) => payload.clone(),
+    }
+}
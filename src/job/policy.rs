@@ -0,0 +1,106 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// What to do with fires that were due while the scheduler was down or the job couldn't keep up.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MisfirePolicy {
+    /// Drop missed fires and continue with the next one in the future.
+    #[default]
+    Skip,
+    /// Run once for the most recent missed fire.
+    RunOnce,
+    /// Run every missed fire, oldest first.
+    RunAll,
+    /// Run once for the most recent missed fire if it is no older than the grace period.
+    Grace(Duration),
+}
+
+impl MisfirePolicy {
+    pub fn is_skip(&self) -> bool {
+        *self == Self::Skip
+    }
+}
+
+/// What to do when a job fires while earlier runs are still in flight.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverlapPolicy {
+    /// Drop the new fire.
+    #[default]
+    Skip,
+    /// Start the new fire once the running one finishes, keeping at most one waiting.
+    Queue,
+    /// Allow up to this many runs at once, dropping fires beyond that.
+    Concurrent(usize),
+    /// Cancel the running ones and start the new fire. Blocking callbacks can't be interrupted
+    /// and finish in the background.
+    Replace,
+}
+
+impl OverlapPolicy {
+    pub fn is_skip(&self) -> bool {
+        *self == Self::Skip
+    }
+}
+
+/// Retries failed, panicked or timed out runs with exponential backoff, independently of the
+/// job's triggers.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_delay: Duration,
+    multiplier: f64,
+    jitter: Duration,
+    max_delay: Duration,
+}
+
+// `with_multiplier` keeps NaN out
+impl Eq for RetryPolicy {}
+
+impl RetryPolicy {
+    /// Makes up to `max_attempts` attempts in total, doubling `initial_delay` between them.
+    pub fn new(max_attempts: u32, initial_delay: Duration) -> Self {
+        Self {
+            max_attempts,
+            initial_delay,
+            multiplier: 2.0,
+            jitter: Duration::ZERO,
+            max_delay: Duration::from_secs(3600),
+        }
+    }
+
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    /// Adds a random delay of up to `jitter` to every retry.
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Delay before the attempt following `attempt`, or `None` once all attempts are used up.
+    pub fn delay(&self, attempt: u32) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        let backoff = self.initial_delay.as_secs_f64()
+            * self.multiplier.powi(attempt.saturating_sub(1) as i32);
+        let delay = Duration::from_secs_f64(backoff.min(self.max_delay.as_secs_f64()));
+        let jitter = match self.jitter.is_zero() {
+            true => Duration::ZERO,
+            false => rand::thread_rng().gen_range(Duration::ZERO..=self.jitter),
+        };
+        Some(delay + jitter)
+    }
+}
@@ -0,0 +1,54 @@
+use crate::error::SchedulerError;
+use crate::job::{Callback, Job};
+
+use serde::de::{DeserializeSeed, Deserializer, Error as _};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Callbacks by name, used to give jobs loaded from storage their behavior back.
+#[derive(Clone, Debug, Default)]
+pub struct CallbackRegistry {
+    callbacks: HashMap<String, Callback>,
+}
+
+impl CallbackRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, name: &str, callback: impl Into<Callback>) -> &mut Self {
+        self.callbacks.insert(name.to_string(), callback.into());
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Callback> {
+        self.callbacks.get(name)
+    }
+
+    pub fn resolve(&self, job: &mut Job) -> Result<(), SchedulerError> {
+        if let Some(name) = &job.callback_name {
+            let callback = self
+                .get(name)
+                .ok_or_else(|| SchedulerError::UnknownCallback(name.clone()))?;
+            job.callback = Some(callback.clone());
+        }
+        Ok(())
+    }
+
+    pub fn job_from_json(&self, json: &str) -> serde_json::Result<Job> {
+        self.deserialize(&mut serde_json::Deserializer::from_str(json))
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for &CallbackRegistry {
+    type Value = Job;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut job = Job::deserialize(deserializer)?;
+        self.resolve(&mut job).map_err(D::Error::custom)?;
+        Ok(job)
+    }
+}
@@ -0,0 +1,51 @@
+use crate::error::JobError;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RunRecord {
+    pub job: String,
+    pub scheduled: DateTime<Utc>,
+    pub started: DateTime<Utc>,
+    pub duration: Duration,
+    pub outcome: std::result::Result<Value, JobError>,
+    /// Starts at 1 and counts up with every retry of the same fire.
+    pub attempt: u32,
+    /// Triggered through the handle rather than by the schedule.
+    pub manual: bool,
+    /// The trigger that fired, serialized like in the job definition. `None` for manual runs.
+    pub trigger: Option<Value>,
+}
+
+impl RunRecord {
+    pub fn finished(&self) -> DateTime<Utc> {
+        self.started + self.duration
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.outcome.is_ok()
+    }
+
+    /// Whether the retry policy applies to this outcome.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self.outcome,
+            Err(JobError::Failed(_) | JobError::Panicked(_) | JobError::TimedOut(_))
+        )
+    }
+
+    pub fn log(&self) {
+        let (name, duration, attempt) = (&self.job, self.duration, self.attempt);
+        match &self.outcome {
+            Ok(_) => info!(name, attempt, ?duration, "run succeeded"),
+            Err(error @ JobError::Panicked(_)) => {
+                error!(name, attempt, ?duration, %error, "run panicked")
+            }
+            Err(error) => warn!(name, attempt, ?duration, %error, "run failed"),
+        }
+    }
+}
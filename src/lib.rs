@@ -1,5 +1,8 @@
+pub mod clock;
+pub mod error;
 pub mod job;
 pub mod scheduler;
+pub mod store;
 pub mod tests;
 pub mod trigger;
 
@@ -0,0 +1,693 @@
+use super::JobInfo;
+use crate::clock::Clock;
+use crate::job::{Job, OverlapPolicy, RunRecord};
+use crate::trigger::Trigger;
+
+use chrono::{DateTime, Duration, Utc};
+use itertools::Itertools;
+use serde_json::Value;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, VecDeque};
+use std::sync::Arc;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::task::{Id, JoinError, JoinSet};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info_span, warn, Instrument};
+
+pub(crate) const DEFAULT_WORKERS: usize = 128;
+
+// how late a timer may go off before its fire counts as missed, e.g. after the clock jumped
+const MISFIRE_TOLERANCE: Duration = Duration::seconds(1);
+
+// a fire of a job, with the trigger behind it and the payload of the event that fired it
+struct Due {
+    scheduled: DateTime<Utc>,
+    trigger: Option<Value>,
+    payload: Option<Value>,
+}
+
+impl Due {
+    fn new(scheduled: DateTime<Utc>, trigger: &dyn Trigger) -> Self {
+        Self {
+            scheduled,
+            trigger: serde_json::to_value(trigger).ok(),
+            payload: None,
+        }
+    }
+}
+
+// a run waiting for a worker, running, or waiting to be retried
+struct Work {
+    job: Arc<Job>,
+    job_id: u64,
+    due: Due,
+    attempt: u32,
+    manual: bool,
+    cancel: CancellationToken,
+}
+
+enum TimerKind {
+    Fire(Due),
+    Retry(Work),
+}
+
+struct Timer {
+    at: DateTime<Utc>,
+    seq: u64,
+    job: String,
+    kind: TimerKind,
+}
+
+impl PartialEq for Timer {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Timer {}
+
+impl PartialOrd for Timer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Timer {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.at, self.seq).cmp(&(other.at, other.seq))
+    }
+}
+
+struct JobState {
+    job: Arc<Job>,
+    // tells a re-added job apart from runs of the one it replaced
+    id: u64,
+    paused: bool,
+    cursor: DateTime<Utc>,
+    next_fire: Option<DateTime<Utc>>,
+    running: usize,
+    // fires waiting for earlier runs
+    pending: VecDeque<Due>,
+    // upstream jobs completed since each dependency trigger last fired, by trigger hash
+    completed: HashMap<String, BTreeSet<String>>,
+    replace: CancellationToken,
+}
+
+struct Group {
+    limit: usize,
+    running: usize,
+}
+
+/// Keeps the next fire of every job in one min-heap, sleeps until the earliest and hands runs
+/// to a bounded pool of worker tasks. Runs of jobs in a concurrency group also need one of the
+/// group's permits.
+pub(crate) struct Dispatcher {
+    clock: Arc<dyn Clock>,
+    jobs: BTreeMap<String, JobState>,
+    timers: BinaryHeap<Reverse<Timer>>,
+    seq: u64,
+    workers: usize,
+    groups: HashMap<String, Group>,
+    // jobs with a dependency trigger on each upstream job, so finished runs only visit those
+    dependents: HashMap<String, BTreeSet<String>>,
+    backlog: VecDeque<Work>,
+    runs: JoinSet<(Work, RunRecord)>,
+    run_jobs: HashMap<Id, Arc<Job>>,
+    results: Option<UnboundedSender<RunRecord>>,
+    shutdown: CancellationToken,
+}
+
+impl Dispatcher {
+    pub(crate) fn new(
+        clock: Arc<dyn Clock>,
+        workers: usize,
+        results: Option<UnboundedSender<RunRecord>>,
+    ) -> Self {
+        Self {
+            clock,
+            jobs: BTreeMap::new(),
+            timers: BinaryHeap::new(),
+            seq: 0,
+            workers: workers.max(1),
+            groups: HashMap::new(),
+            dependents: HashMap::new(),
+            backlog: VecDeque::new(),
+            runs: JoinSet::new(),
+            run_jobs: HashMap::new(),
+            results,
+            shutdown: CancellationToken::new(),
+        }
+    }
+
+    pub(crate) fn add_group(&mut self, name: &str, limit: usize) {
+        self.groups.insert(
+            name.to_string(),
+            Group {
+                limit: limit.max(1),
+                running: 0,
+            },
+        );
+    }
+
+    pub(crate) fn contains(&self, name: &str) -> bool {
+        self.jobs.contains_key(name)
+    }
+
+    pub(crate) fn job(&self, name: &str) -> Option<&Job> {
+        self.jobs.get(name).map(|state| state.job.as_ref())
+    }
+
+    /// Nothing is scheduled, waiting for a worker or running.
+    pub(crate) fn is_idle(&self) -> bool {
+        self.timers.is_empty() && self.backlog.is_empty() && self.runs.is_empty()
+    }
+
+    /// Schedules `job` from `since`, treating fires before the current time as missed.
+    pub(crate) fn insert(&mut self, job: Arc<Job>, since: Option<DateTime<Utc>>) {
+        let name = job.name.clone();
+        if let Some(group) = job
+            .concurrency_group()
+            .filter(|group| !self.groups.contains_key(*group))
+        {
+            warn!(
+                name,
+                group, "unknown concurrency group, running without its limit"
+            );
+        }
+        // a re-added job may depend on other jobs than the one it replaces
+        self.forget_dependencies(&name);
+        for upstream in job.triggers().iter().flat_map(|trigger| trigger.upstream()) {
+            self.dependents
+                .entry(upstream.to_string())
+                .or_default()
+                .insert(name.clone());
+        }
+        self.seq += 1;
+        let paused = job.is_paused();
+        self.jobs.insert(
+            name.clone(),
+            JobState {
+                job,
+                id: self.seq,
+                paused,
+                cursor: since.unwrap_or_else(|| self.clock.now()),
+                next_fire: None,
+                running: 0,
+                pending: VecDeque::new(),
+                completed: HashMap::new(),
+                replace: CancellationToken::new(),
+            },
+        );
+        self.schedule(&name);
+    }
+
+    /// Forgets the job. Its in-flight runs still finish and get reported, runs still waiting for
+    /// a worker or permit are dropped.
+    pub(crate) fn remove(&mut self, name: &str) -> bool {
+        self.timers.retain(|Reverse(timer)| timer.job != name);
+        let Some(state) = self.jobs.remove(name) else {
+            return false;
+        };
+        self.forget_dependencies(name);
+        self.drop_backlog(name, state.id);
+        true
+    }
+
+    fn forget_dependencies(&mut self, name: &str) {
+        self.dependents.retain(|_, dependents| {
+            dependents.remove(name);
+            !dependents.is_empty()
+        });
+    }
+
+    pub(crate) fn pause(&mut self, name: &str) -> bool {
+        let Some(job_id) = self.jobs.get(name).map(|state| state.id) else {
+            return false;
+        };
+        let dropped = self.drop_backlog(name, job_id);
+        let Some(state) = self.jobs.get_mut(name) else {
+            return false;
+        };
+        state.paused = true;
+        state.next_fire = None;
+        state.pending.clear();
+        state.running -= dropped;
+        self.timers.retain(|Reverse(timer)| {
+            timer.job != name || matches!(timer.kind, TimerKind::Retry(_))
+        });
+        true
+    }
+
+    pub(crate) fn resume(&mut self, name: &str) -> bool {
+        let now = self.clock.now();
+        let Some(state) = self.jobs.get_mut(name) else {
+            return false;
+        };
+        if state.paused {
+            state.paused = false;
+            // fires while paused were skipped on purpose
+            state.cursor = now;
+            self.schedule(name);
+        }
+        true
+    }
+
+    /// Runs the job now, regardless of its schedule and overlap policy.
+    pub(crate) fn trigger(&mut self, name: &str) -> bool {
+        let now = self.clock.now();
+        if !self.contains(name) {
+            return false;
+        }
+        let due = Due {
+            scheduled: now,
+            trigger: None,
+            payload: None,
+        };
+        self.start(name, due, true);
+        true
+    }
+
+    /// Fires the jobs listening for `event` with `payload`, returning their names.
+    pub(crate) fn emit(&mut self, event: &str, payload: &Value) -> Vec<String> {
+        if self.shutdown.is_cancelled() {
+            return Vec::new();
+        }
+        let now = self.clock.now();
+        let fired: Vec<(String, Due)> = self
+            .jobs
+            .iter()
+            .filter(|(_, state)| !state.paused)
+            .filter_map(|(name, state)| {
+                let trigger = state
+                    .job
+                    .triggers()
+                    .iter()
+                    .find(|trigger| trigger.listens_to(event))?;
+                let due = Due {
+                    payload: Some(payload.clone()),
+                    ..Due::new(now, trigger.as_ref())
+                };
+                Some((name.clone(), due))
+            })
+            .collect();
+
+        fired
+            .into_iter()
+            .map(|(name, due)| {
+                debug!(name, event, "event received");
+                self.admit(&name, due);
+                name
+            })
+            .collect()
+    }
+
+    pub(crate) fn job_infos(&self) -> Vec<JobInfo> {
+        self.jobs
+            .iter()
+            .map(|(name, state)| JobInfo {
+                name: name.clone(),
+                paused: state.paused,
+                next_run: state.next_fire,
+            })
+            .collect()
+    }
+
+    /// Stops scheduling fires and retries, leaving started runs to finish.
+    pub(crate) fn shutdown(&mut self) {
+        self.shutdown.cancel();
+        self.timers.clear();
+        for state in self.jobs.values_mut() {
+            state.next_fire = None;
+            state.pending.clear();
+        }
+        if !self.backlog.is_empty() {
+            warn!(
+                dropped = self.backlog.len(),
+                "dropping runs still waiting for a worker or permit"
+            );
+            self.backlog.clear();
+        }
+    }
+
+    /// Aborts whatever is still running and reports which jobs it belonged to.
+    pub(crate) fn interrupt(&mut self) -> Vec<String> {
+        self.runs.abort_all();
+        std::mem::take(&mut self.run_jobs)
+            .into_values()
+            .map(|job| job.name.clone())
+            .sorted()
+            .dedup()
+            .collect()
+    }
+
+    /// Waits for the earliest timer or the next finished run and handles it. Cancel safe.
+    pub(crate) async fn step(&mut self) {
+        let sleep = match self.timers.peek() {
+            Some(Reverse(timer)) => self.clock.sleep_until(timer.at),
+            None => Box::pin(std::future::pending()),
+        };
+        tokio::select! {
+            Some(finished) = self.runs.join_next_with_id() => self.finished(finished),
+            _ = sleep => self.expire_timers(),
+        }
+    }
+
+    // computes the job's next fire from its cursor, queueing missed runs for replay
+    fn schedule(&mut self, name: &str) {
+        let now = self.clock.now();
+        let Some(state) = self.jobs.get_mut(name) else {
+            return;
+        };
+        state.next_fire = None;
+        if state.paused || self.shutdown.is_cancelled() {
+            return;
+        }
+        loop {
+            let Some((trigger, next_fire)) = state.job.triggers().next_fire_after(state.cursor)
+            else {
+                debug!(name, "no more runs");
+                break;
+            };
+            if next_fire >= now {
+                debug!(name, at = next_fire.to_rfc3339(), "next run");
+                state.next_fire = Some(next_fire);
+                self.seq += 1;
+                self.timers.push(Reverse(Timer {
+                    at: next_fire,
+                    seq: self.seq,
+                    job: name.to_string(),
+                    kind: TimerKind::Fire(Due::new(next_fire, trigger)),
+                }));
+                break;
+            }
+            let (runs, resume) = state.job.misfired((trigger, next_fire), now);
+            // missed runs are replayed one after another, whatever the overlap policy
+            state.pending.extend(
+                runs.into_iter()
+                    .map(|(trigger, scheduled)| Due::new(scheduled, trigger)),
+            );
+            state.cursor = resume;
+        }
+        self.start_pending(name);
+    }
+
+    fn expire_timers(&mut self) {
+        let now = self.clock.now();
+        // the earliest timer is what the sleep was for, even if the clock disagrees slightly
+        while let Some(Reverse(timer)) = self.timers.pop() {
+            self.expire(timer);
+            if self
+                .timers
+                .peek()
+                .is_none_or(|Reverse(timer)| timer.at > now)
+            {
+                break;
+            }
+        }
+    }
+
+    fn expire(&mut self, timer: Timer) {
+        match timer.kind {
+            TimerKind::Fire(due) => self.fire(&timer.job, due),
+            TimerKind::Retry(work) => {
+                let current = self
+                    .jobs
+                    .get(&timer.job)
+                    .is_some_and(|state| state.id == work.job_id);
+                if current && !work.cancel.is_cancelled() {
+                    self.submit(work);
+                } else {
+                    self.run_finished(&timer.job, work.job_id);
+                }
+            }
+        }
+    }
+
+    fn fire(&mut self, name: &str, due: Due) {
+        let now = self.clock.now();
+        let Some(state) = self.jobs.get_mut(name) else {
+            return;
+        };
+        if now - due.scheduled > MISFIRE_TOLERANCE {
+            // the cursor is still before the fire, so it goes through the misfire policy
+            self.schedule(name);
+            return;
+        }
+        state.cursor = due.scheduled;
+        debug!(name, "triggered");
+        self.admit(name, due);
+        self.schedule(name);
+    }
+
+    // starts, queues or skips a fire under the job's overlap policy
+    fn admit(&mut self, name: &str, due: Due) {
+        let Some(state) = self.jobs.get_mut(name) else {
+            return;
+        };
+        let running = state.running;
+        let start = match state.job.overlap_policy() {
+            _ if running == 0 => true,
+            OverlapPolicy::Concurrent(limit) if running < limit => true,
+            OverlapPolicy::Queue if state.pending.is_empty() => {
+                debug!(name, "previous run still in flight, queueing");
+                state.pending.push_back(due);
+                return;
+            }
+            OverlapPolicy::Replace => {
+                warn!(name, running, "replacing in-flight runs");
+                state.replace.cancel();
+                state.replace = CancellationToken::new();
+                true
+            }
+            policy => {
+                warn!(
+                    name,
+                    running,
+                    ?policy,
+                    "previous runs still in flight, skipping"
+                );
+                false
+            }
+        };
+        if start {
+            self.start(name, due, false);
+        }
+    }
+
+    // fires the jobs whose dependencies have all completed, `record` being the latest of them
+    fn notify(&mut self, record: &RunRecord) {
+        if self.shutdown.is_cancelled() {
+            return;
+        }
+        let now = self.clock.now();
+        let mut fired = Vec::new();
+        let dependents = self.dependents.get(&record.job).into_iter().flatten();
+        for name in dependents {
+            let Some(state) = self.jobs.get_mut(name).filter(|state| !state.paused) else {
+                continue;
+            };
+            for trigger in state.job.triggers().iter() {
+                if !trigger.completes(record) {
+                    continue;
+                }
+                let key = trigger.hash();
+                let completed = state.completed.entry(key.clone()).or_default();
+                completed.insert(record.job.clone());
+                if trigger
+                    .upstream()
+                    .iter()
+                    .all(|job| completed.contains(*job))
+                {
+                    state.completed.remove(&key);
+                    fired.push((name.clone(), Due::new(now, trigger.as_ref())));
+                }
+            }
+        }
+
+        for (name, due) in fired {
+            debug!(name, upstream = record.job, "dependencies completed");
+            self.admit(&name, due);
+        }
+    }
+
+    fn start(&mut self, name: &str, due: Due, manual: bool) {
+        let Some(state) = self.jobs.get_mut(name) else {
+            return;
+        };
+        state.running += 1;
+        let work = Work {
+            job: state.job.clone(),
+            job_id: state.id,
+            due,
+            attempt: 1,
+            manual,
+            cancel: state.replace.clone(),
+        };
+        self.submit(work);
+    }
+
+    fn start_pending(&mut self, name: &str) {
+        let Some(state) = self.jobs.get_mut(name) else {
+            return;
+        };
+        if state.running == 0 {
+            if let Some(due) = state.pending.pop_front() {
+                self.start(name, due, false);
+            }
+        }
+    }
+
+    // removes the job's runs waiting for a worker or permit, returning how many there were
+    fn drop_backlog(&mut self, name: &str, job_id: u64) -> usize {
+        let waiting = self.backlog.len();
+        self.backlog
+            .retain(|work| work.job.name != name || work.job_id != job_id);
+        let dropped = waiting - self.backlog.len();
+        if dropped > 0 {
+            debug!(
+                name,
+                dropped, "dropping runs waiting for a worker or permit"
+            );
+        }
+        dropped
+    }
+
+    fn submit(&mut self, work: Work) {
+        self.backlog.push_back(work);
+        self.dispatch();
+    }
+
+    // starts waiting runs in order while workers are free, passing over runs whose group is full
+    fn dispatch(&mut self) {
+        let mut index = 0;
+        while index < self.backlog.len() && self.runs.len() < self.workers {
+            let group = self.backlog[index]
+                .job
+                .concurrency_group()
+                .and_then(|group| self.groups.get_mut(group));
+            match group {
+                Some(group) if group.running >= group.limit => index += 1,
+                group => {
+                    if let Some(group) = group {
+                        group.running += 1;
+                    }
+                    if let Some(work) = self.backlog.remove(index) {
+                        self.spawn(work);
+                    }
+                }
+            }
+        }
+    }
+
+    fn spawn(&mut self, work: Work) {
+        let job = work.job.clone();
+        let name = job.name.clone();
+        let clock = self.clock.clone();
+        let stop = self.shutdown.child_token();
+        let span = info_span!(
+            "run",
+            name,
+            scheduled = work.due.scheduled.to_rfc3339(),
+            attempt = work.attempt
+        );
+        let run = self.runs.spawn(
+            async move {
+                let due = &work.due;
+                let mut record = work
+                    .job
+                    .execute(
+                        clock.as_ref(),
+                        due.scheduled,
+                        due.payload.as_ref(),
+                        stop,
+                        &work.cancel,
+                    )
+                    .await;
+                record.attempt = work.attempt;
+                record.manual = work.manual;
+                record.trigger = due.trigger.clone();
+                (work, record)
+            }
+            .instrument(span),
+        );
+        self.run_jobs.insert(run.id(), job);
+    }
+
+    fn release(&mut self, job: &Job) {
+        if let Some(group) = job
+            .concurrency_group()
+            .and_then(|group| self.groups.get_mut(group))
+        {
+            group.running -= 1;
+        }
+    }
+
+    fn finished(&mut self, finished: Result<(Id, (Work, RunRecord)), JoinError>) {
+        match finished {
+            Ok((id, (work, record))) => {
+                self.run_jobs.remove(&id);
+                self.release(&work.job);
+                self.completed(work, record);
+            }
+            Err(error) => {
+                if let Some(job) = self.run_jobs.remove(&error.id()) {
+                    let name = &job.name;
+                    error!(name, %error, "run task failed");
+                    self.release(&job);
+                    // without the work item, the job can't be told apart from a re-added one
+                    if let Some(state) = self.jobs.get_mut(name) {
+                        state.running = state.running.saturating_sub(1);
+                    }
+                }
+            }
+        }
+        self.dispatch();
+    }
+
+    fn completed(&mut self, mut work: Work, record: RunRecord) {
+        let name = record.job.clone();
+        let retry_delay = work
+            .job
+            .retry_policy()
+            .filter(|_| record.is_retryable() && !self.shutdown.is_cancelled())
+            .and_then(|retry_policy| retry_policy.delay(work.attempt))
+            .filter(|_| !work.cancel.is_cancelled());
+        if retry_delay.is_none() {
+            self.notify(&record);
+        }
+        match &self.results {
+            Some(results) => {
+                let _ = results.send(record);
+            }
+            None => record.log(),
+        }
+
+        match retry_delay {
+            Some(retry_delay) => {
+                warn!(
+                    name,
+                    attempt = work.attempt,
+                    ?retry_delay,
+                    "retrying failed run"
+                );
+                work.attempt += 1;
+                self.seq += 1;
+                self.timers.push(Reverse(Timer {
+                    at: self.clock.now() + retry_delay,
+                    seq: self.seq,
+                    job: name,
+                    kind: TimerKind::Retry(work),
+                }));
+            }
+            None => self.run_finished(&name, work.job_id),
+        }
+    }
+
+    fn run_finished(&mut self, name: &str, job_id: u64) {
+        let Some(state) = self.jobs.get_mut(name).filter(|state| state.id == job_id) else {
+            return;
+        };
+        state.running -= 1;
+        self.start_pending(name);
+    }
+}
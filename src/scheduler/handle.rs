@@ -0,0 +1,111 @@
+use crate::error::SchedulerError;
+use crate::job::{Job, JobStats, RunRecord};
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+
+type Reply<T> = oneshot::Sender<Result<T, SchedulerError>>;
+
+pub(crate) enum Command {
+    AddJob(Box<Job>, Reply<()>),
+    RemoveJob(String, Reply<()>),
+    PauseJob(String, Reply<()>),
+    ResumeJob(String, Reply<()>),
+    TriggerJob(String, Reply<()>),
+    ListJobs(Reply<Vec<JobInfo>>),
+    History(String, Reply<Vec<RunRecord>>),
+    Stats(String, Reply<JobStats>),
+    Emit(String, Value, Reply<Vec<String>>),
+    Shutdown(Duration, Reply<ShutdownSummary>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobInfo {
+    pub name: String,
+    pub paused: bool,
+    pub next_run: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShutdownSummary {
+    /// Jobs whose runs were still in flight when the drain deadline passed.
+    pub interrupted: Vec<String>,
+}
+
+#[derive(Clone)]
+pub struct SchedulerHandle {
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+impl SchedulerHandle {
+    pub(crate) fn new(commands: mpsc::UnboundedSender<Command>) -> Self {
+        Self { commands }
+    }
+
+    async fn request<T>(
+        &self,
+        command: impl FnOnce(Reply<T>) -> Command,
+    ) -> Result<T, SchedulerError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.commands
+            .send(command(reply_tx))
+            .map_err(|_| SchedulerError::Stopped)?;
+        reply_rx.await.map_err(|_| SchedulerError::Stopped)?
+    }
+
+    pub async fn add_job(&self, job: Job) -> Result<(), SchedulerError> {
+        self.request(|reply| Command::AddJob(Box::new(job), reply))
+            .await
+    }
+
+    pub async fn remove_job(&self, name: &str) -> Result<(), SchedulerError> {
+        self.request(|reply| Command::RemoveJob(name.to_string(), reply))
+            .await
+    }
+
+    pub async fn pause_job(&self, name: &str) -> Result<(), SchedulerError> {
+        self.request(|reply| Command::PauseJob(name.to_string(), reply))
+            .await
+    }
+
+    pub async fn resume_job(&self, name: &str) -> Result<(), SchedulerError> {
+        self.request(|reply| Command::ResumeJob(name.to_string(), reply))
+            .await
+    }
+
+    pub async fn trigger_job(&self, name: &str) -> Result<(), SchedulerError> {
+        self.request(|reply| Command::TriggerJob(name.to_string(), reply))
+            .await
+    }
+
+    pub async fn jobs(&self) -> Result<Vec<JobInfo>, SchedulerError> {
+        self.request(Command::ListJobs).await
+    }
+
+    /// Fires every job with an [`Event`](crate::trigger::Event) trigger for `event`, merging
+    /// `payload` into their callback context for that run. Returns the names of the fired jobs.
+    pub async fn emit(&self, event: &str, payload: Value) -> Result<Vec<String>, SchedulerError> {
+        self.request(|reply| Command::Emit(event.to_string(), payload, reply))
+            .await
+    }
+
+    /// The job's latest runs, oldest first.
+    pub async fn history(&self, name: &str) -> Result<Vec<RunRecord>, SchedulerError> {
+        self.request(|reply| Command::History(name.to_string(), reply))
+            .await
+    }
+
+    pub async fn stats(&self, name: &str) -> Result<JobStats, SchedulerError> {
+        self.request(|reply| Command::Stats(name.to_string(), reply))
+            .await
+    }
+
+    /// Stops scheduling new runs and waits up to `deadline` for in-flight runs to finish
+    /// before aborting them.
+    pub async fn shutdown(&self, deadline: Duration) -> Result<ShutdownSummary, SchedulerError> {
+        self.request(|reply| Command::Shutdown(deadline, reply))
+            .await
+    }
+}
@@ -1,42 +1,208 @@
-use crate::job::{Job, Result};
-use tokio::task::JoinSet;
+pub(crate) mod dispatcher;
+pub mod handle;
+pub mod preview;
+mod writer;
+
+use crate::clock::{Clock, SystemClock};
+use crate::error::SchedulerError;
+use crate::job::{CallbackRegistry, Job, RunHistory, RunRecord};
+use crate::store::JobStore;
+use crate::trigger::{Calendar, Calendars};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::sleep;
 use tracing::{error, info, warn};
 
+use self::dispatcher::{Dispatcher, DEFAULT_WORKERS};
+use self::handle::Command;
+pub use self::handle::{JobInfo, SchedulerHandle, ShutdownSummary};
+pub use self::preview::Fire;
+use self::writer::StoreWriter;
+
+const RESULTS_CAPACITY: usize = 1024;
+const DEFAULT_HISTORY_SIZE: usize = 100;
+
 pub struct Scheduler {
     jobs: Vec<Job>,
+    clock: Arc<dyn Clock>,
+    workers: usize,
+    groups: HashMap<String, usize>,
+    history_size: usize,
+    store: Option<Arc<dyn JobStore>>,
+    registry: CallbackRegistry,
+    calendars: Calendars,
+    results: broadcast::Sender<RunRecord>,
+    commands_tx: mpsc::UnboundedSender<Command>,
+    commands_rx: mpsc::UnboundedReceiver<Command>,
 }
 
 impl Scheduler {
     pub fn new() -> Self {
-        Self { jobs: Vec::new() }
+        let (commands_tx, commands_rx) = mpsc::unbounded_channel();
+        Self {
+            jobs: Vec::new(),
+            clock: Arc::new(SystemClock),
+            workers: DEFAULT_WORKERS,
+            groups: HashMap::new(),
+            history_size: DEFAULT_HISTORY_SIZE,
+            store: None,
+            registry: CallbackRegistry::new(),
+            calendars: Calendars::new(),
+            results: broadcast::channel(RESULTS_CAPACITY).0,
+            commands_tx,
+            commands_rx,
+        }
+    }
+
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Caps how many runs execute at once across all jobs. Fires beyond that wait for a free
+    /// worker.
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        self.workers = workers;
+        self
+    }
+
+    /// Lets at most `limit` runs of the jobs in group `name` execute at once, on top of the
+    /// global limit. Jobs join with [`Job::with_concurrency_group`].
+    pub fn with_concurrency_group(mut self, name: &str, limit: usize) -> Self {
+        self.groups.insert(name.to_string(), limit);
+        self
+    }
+
+    /// Keeps the latest `history_size` runs of every job, see [`SchedulerHandle::history`].
+    pub fn with_history_size(mut self, history_size: usize) -> Self {
+        self.history_size = history_size;
+        self
+    }
+
+    /// Restores jobs from `store` when the scheduler starts and writes every change back to it.
+    pub fn with_store(mut self, store: impl JobStore + 'static) -> Self {
+        self.store = Some(Arc::new(store));
+        self
     }
 
-    pub fn add_job(&mut self, job: Job) {
+    /// Resolves the callbacks of registered jobs, including jobs restored from the store.
+    pub fn with_registry(mut self, registry: CallbackRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Shares `calendar` under `name` with every [`Exclude`](crate::trigger::Exclude) trigger
+    /// that refers to it, including those of jobs restored from the store. Calendars are looked
+    /// up once the scheduler runs, so jobs can be added before their calendars.
+    pub fn with_calendar(mut self, name: &str, calendar: Calendar) -> Self {
+        self.calendars.register(name, calendar);
+        self
+    }
+
+    pub fn add_job(&mut self, mut job: Job) -> std::result::Result<(), SchedulerError> {
+        if self.jobs.iter().any(|j| j.name == job.name) {
+            return Err(SchedulerError::DuplicateJob(job.name));
+        }
+        if let Some(cycle) =
+            dependency_cycle(&job, &|name| self.jobs.iter().find(|job| job.name == name))
+        {
+            return Err(SchedulerError::DependencyCycle(cycle));
+        }
+        self.registry.resolve(&mut job)?;
         self.jobs.push(job);
+        Ok(())
+    }
+
+    pub fn handle(&self) -> SchedulerHandle {
+        SchedulerHandle::new(self.commands_tx.clone())
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<RunRecord> {
+        self.results.subscribe()
     }
 
     pub async fn run(self) {
-        let mut tasks = JoinSet::<Result<()>>::new();
-        for job in self.jobs {
-            Job::run(job, &mut tasks);
-        }
-
-        while !tasks.is_empty() {
-            match tasks.join_next().await {
-                Some(result) => match result {
-                    Ok(task_return) => match task_return {
-                        Ok(_) => {
-                            info!("task finished")
-                        }
-                        Err(error) => warn!(%error, "task returned with error"),
-                    },
-                    Err(error) => error!(%error, "task panicked"),
+        let Scheduler {
+            jobs,
+            clock,
+            workers,
+            groups,
+            history_size,
+            store,
+            registry,
+            calendars,
+            results,
+            commands_tx,
+            mut commands_rx,
+        } = self;
+        // only handles keep the command channel open from here on
+        drop(commands_tx);
+
+        let (results_tx, mut results_rx) = mpsc::unbounded_channel();
+        let mut dispatcher = Dispatcher::new(clock.clone(), workers, Some(results_tx));
+        for (name, limit) in &groups {
+            dispatcher.add_group(name, *limit);
+        }
+        let mut runner = Runner {
+            dispatcher,
+            histories: HashMap::new(),
+            history_size,
+            clock,
+            writer: store.clone().map(StoreWriter::spawn),
+            store,
+            registry,
+            calendars,
+            results,
+        };
+        runner.restore(jobs).await;
+
+        let mut commands_open = true;
+        let mut shutdown = None;
+        while commands_open || !runner.dispatcher.is_idle() {
+            tokio::select! {
+                command = commands_rx.recv(), if commands_open => match command {
+                    Some(Command::Shutdown(deadline, reply)) => {
+                        shutdown = Some((deadline, reply));
+                        break;
+                    }
+                    Some(command) => runner.handle_command(command).await,
+                    None => commands_open = false,
                 },
-                None => {
-                    info!("no more tasks to run, shutting down")
+                Some(run_record) = results_rx.recv() => runner.record(run_record),
+                _ = runner.dispatcher.step(), if !runner.dispatcher.is_idle() => {}
+            }
+        }
+
+        if let Some((deadline, reply)) = shutdown {
+            info!(?deadline, "shutting down, draining in-flight runs");
+            runner.dispatcher.shutdown();
+            let deadline = sleep(deadline);
+            tokio::pin!(deadline);
+            while !runner.dispatcher.is_idle() {
+                tokio::select! {
+                    Some(run_record) = results_rx.recv() => runner.record(run_record),
+                    _ = runner.dispatcher.step() => {}
+                    _ = &mut deadline => break,
                 }
             }
+
+            let summary = ShutdownSummary {
+                interrupted: runner.dispatcher.interrupt(),
+            };
+            if !summary.interrupted.is_empty() {
+                warn!(interrupted = ?summary.interrupted, "interrupted in-flight runs");
+            }
+            let _ = reply.send(Ok(summary));
+        }
+
+        while let Ok(run_record) = results_rx.try_recv() {
+            runner.record(run_record);
+        }
+        if let Some(writer) = runner.writer.take() {
+            writer.close().await;
         }
+        info!("no more tasks to run, shutting down")
     }
 }
 
@@ -45,3 +211,271 @@ impl Default for Scheduler {
         Self::new()
     }
 }
+
+struct Runner {
+    dispatcher: Dispatcher,
+    histories: HashMap<String, RunHistory>,
+    history_size: usize,
+    clock: Arc<dyn Clock>,
+    store: Option<Arc<dyn JobStore>>,
+    writer: Option<StoreWriter>,
+    registry: CallbackRegistry,
+    calendars: Calendars,
+    results: broadcast::Sender<RunRecord>,
+}
+
+impl Runner {
+    // jobs added in code take precedence over stored definitions but keep their stored state
+    async fn restore(&mut self, mut jobs: Vec<Job>) {
+        let mut stored = match &self.store {
+            Some(store) => match store.load_all().await {
+                Ok(stored) => stored,
+                Err(error) => {
+                    error!(%error, "failed to load jobs from store");
+                    Vec::new()
+                }
+            },
+            None => Vec::new(),
+        };
+
+        for job in jobs.iter_mut() {
+            if let Some(stored_job) = stored.iter().find(|stored| stored.name == job.name) {
+                if job.last_run().is_none() {
+                    job.set_last_run(stored_job.last_run());
+                }
+                job.set_paused(stored_job.is_paused());
+            }
+        }
+        stored.retain(|stored| !jobs.iter().any(|job| job.name == stored.name));
+
+        for job in jobs {
+            if let Err(error) = self.add_job(job).await {
+                error!(%error, "failed to add job");
+            }
+        }
+        for mut job in stored {
+            if let Some(cycle) = dependency_cycle(&job, &|name| self.dispatcher.job(name)) {
+                let error = SchedulerError::DependencyCycle(cycle);
+                error!(name = job.name, %error, "failed to restore job");
+                continue;
+            }
+            let resolved = self.registry.resolve(&mut job);
+            match resolved.and_then(|_| self.calendars.resolve(&job)) {
+                Ok(_) => {
+                    info!(name = job.name, "restored job from store");
+                    self.insert_job(job);
+                }
+                Err(error) => error!(name = job.name, %error, "failed to restore job"),
+            }
+        }
+
+        let histories = match &self.store {
+            Some(store) => store.load_histories().await.unwrap_or_else(|error| {
+                error!(%error, "failed to load run histories from store");
+                Default::default()
+            }),
+            None => Default::default(),
+        };
+        for (name, mut history) in histories {
+            if self.dispatcher.contains(&name) {
+                history.set_capacity(self.history_size);
+                self.histories.insert(name, history);
+            }
+        }
+    }
+
+    fn record(&mut self, record: RunRecord) {
+        record.log();
+        let last_run = (!record.manual).then_some(record.scheduled);
+        // runs of removed jobs are reported but not kept
+        let history = match self.dispatcher.contains(&record.job) {
+            true => {
+                let history_size = self.history_size;
+                let history = self
+                    .histories
+                    .entry(record.job.clone())
+                    .or_insert_with(|| RunHistory::new(history_size));
+                history.push(record.clone());
+                Some(history)
+            }
+            false => None,
+        };
+        if let Some(writer) = &self.writer {
+            writer.record(&record.job, last_run, history.cloned());
+        }
+        let _ = self.results.send(record);
+    }
+
+    async fn handle_command(&mut self, command: Command) {
+        match command {
+            Command::AddJob(job, reply) => {
+                let _ = reply.send(self.add_job(*job).await);
+            }
+            Command::RemoveJob(name, reply) => {
+                let _ = reply.send(self.remove_job(&name).await);
+            }
+            Command::PauseJob(name, reply) => {
+                let _ = reply.send(self.pause_job(&name).await);
+            }
+            Command::ResumeJob(name, reply) => {
+                let _ = reply.send(self.resume_job(&name).await);
+            }
+            Command::TriggerJob(name, reply) => {
+                let _ = reply.send(self.trigger_job(&name));
+            }
+            Command::ListJobs(reply) => {
+                let _ = reply.send(Ok(self.list_jobs()));
+            }
+            Command::Emit(event, payload, reply) => {
+                let _ = reply.send(Ok(self.dispatcher.emit(&event, &payload)));
+            }
+            Command::History(name, reply) => {
+                let _ = reply.send(self.history(&name).map(|history| {
+                    history
+                        .map(|history| history.records().cloned().collect())
+                        .unwrap_or_default()
+                }));
+            }
+            Command::Stats(name, reply) => {
+                let _ = reply.send(
+                    self.history(&name)
+                        .map(|history| history.map(RunHistory::stats).unwrap_or_default()),
+                );
+            }
+            Command::Shutdown(..) => unreachable!("shutdown is handled by the run loop"),
+        }
+    }
+
+    async fn add_job(&mut self, mut job: Job) -> std::result::Result<(), SchedulerError> {
+        if self.dispatcher.contains(&job.name) {
+            return Err(SchedulerError::DuplicateJob(job.name));
+        }
+        if let Some(cycle) = dependency_cycle(&job, &|name| self.dispatcher.job(name)) {
+            return Err(SchedulerError::DependencyCycle(cycle));
+        }
+        self.registry.resolve(&mut job)?;
+        self.calendars.resolve(&job)?;
+        // anchors misfire detection for jobs that never ran
+        if job.last_run().is_none() {
+            job.set_last_run(Some(self.clock.now()));
+        }
+        if let Some(writer) = &self.writer {
+            writer.flush().await;
+        }
+        if let Some(store) = &self.store {
+            store
+                .upsert(&job)
+                .await
+                .map_err(|error| SchedulerError::Store(error.to_string()))?;
+        }
+        info!(name = job.name, "adding job");
+        self.insert_job(job);
+        Ok(())
+    }
+
+    fn insert_job(&mut self, job: Job) {
+        let since = job.last_run();
+        self.dispatcher.insert(Arc::new(job), since);
+    }
+
+    async fn remove_job(&mut self, name: &str) -> std::result::Result<(), SchedulerError> {
+        if !self.dispatcher.contains(name) {
+            return Err(SchedulerError::UnknownJob(name.to_string()));
+        }
+        if let Some(writer) = &self.writer {
+            writer.flush().await;
+        }
+        if let Some(store) = &self.store {
+            store
+                .delete(name)
+                .await
+                .map_err(|error| SchedulerError::Store(error.to_string()))?;
+        }
+        info!(name, "removing job");
+        self.dispatcher.remove(name);
+        self.histories.remove(name);
+        Ok(())
+    }
+
+    async fn pause_job(&mut self, name: &str) -> std::result::Result<(), SchedulerError> {
+        if !self.dispatcher.pause(name) {
+            return Err(SchedulerError::UnknownJob(name.to_string()));
+        }
+        info!(name, "pausing job");
+        self.record_paused(name, true).await
+    }
+
+    async fn resume_job(&mut self, name: &str) -> std::result::Result<(), SchedulerError> {
+        if !self.dispatcher.resume(name) {
+            return Err(SchedulerError::UnknownJob(name.to_string()));
+        }
+        info!(name, "resuming job");
+        self.record_paused(name, false).await
+    }
+
+    async fn record_paused(
+        &self,
+        name: &str,
+        paused: bool,
+    ) -> std::result::Result<(), SchedulerError> {
+        match &self.store {
+            Some(store) => store
+                .record_paused(name, paused)
+                .await
+                .map_err(|error| SchedulerError::Store(error.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    fn trigger_job(&mut self, name: &str) -> std::result::Result<(), SchedulerError> {
+        if !self.dispatcher.trigger(name) {
+            return Err(SchedulerError::UnknownJob(name.to_string()));
+        }
+        info!(name, "triggering job");
+        Ok(())
+    }
+
+    fn history(&self, name: &str) -> std::result::Result<Option<&RunHistory>, SchedulerError> {
+        match self.dispatcher.contains(name) {
+            true => Ok(self.histories.get(name)),
+            false => Err(SchedulerError::UnknownJob(name.to_string())),
+        }
+    }
+
+    fn list_jobs(&self) -> Vec<JobInfo> {
+        self.dispatcher.job_infos()
+    }
+}
+
+// the chain of dependencies leading from `job` back to itself, if there is one
+fn dependency_cycle<'a>(
+    job: &'a Job,
+    lookup: &dyn Fn(&str) -> Option<&'a Job>,
+) -> Option<Vec<String>> {
+    fn visit<'a>(
+        job: &'a Job,
+        target: &str,
+        lookup: &dyn Fn(&str) -> Option<&'a Job>,
+        path: &mut Vec<String>,
+        visited: &mut HashSet<String>,
+    ) -> bool {
+        for upstream in job.triggers().iter().flat_map(|trigger| trigger.upstream()) {
+            path.push(upstream.to_string());
+            if upstream == target {
+                return true;
+            }
+            if visited.insert(upstream.to_string()) {
+                if let Some(upstream_job) = lookup(upstream) {
+                    if visit(upstream_job, target, lookup, path, visited) {
+                        return true;
+                    }
+                }
+            }
+            path.pop();
+        }
+        false
+    }
+
+    let mut path = vec![job.name.clone()];
+    visit(job, &job.name, lookup, &mut path, &mut HashSet::new()).then_some(path)
+}
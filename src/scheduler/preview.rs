@@ -0,0 +1,35 @@
+use super::Scheduler;
+use crate::trigger::Trigger;
+
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+
+/// A fire the scheduler would run, as returned by [`Scheduler::preview`].
+#[derive(Debug, Clone, Copy)]
+pub struct Fire<'a> {
+    pub job: &'a str,
+    pub trigger: &'a dyn Trigger,
+    pub at: DateTime<Utc>,
+}
+
+impl Scheduler {
+    /// Fires of all added jobs in `[from, until)`, in order, without running anything. Jobs
+    /// whose calendars are missing or conflict with the scheduler's have no fires.
+    pub fn preview(&self, from: DateTime<Utc>, until: DateTime<Utc>) -> Vec<Fire<'_>> {
+        self.jobs
+            .iter()
+            .filter(|job| self.calendars.resolve(job).is_ok())
+            .map(|job| {
+                job.triggers()
+                    .preview(from, until)
+                    .into_iter()
+                    .map(|(trigger, at)| Fire {
+                        job: &job.name,
+                        trigger,
+                        at,
+                    })
+            })
+            .kmerge_by(|a, b| a.at < b.at)
+            .collect()
+    }
+}
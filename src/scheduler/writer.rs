@@ -0,0 +1,99 @@
+use crate::job::RunHistory;
+use crate::store::JobStore;
+
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tracing::error;
+
+enum Write {
+    Run {
+        job: String,
+        last_run: Option<DateTime<Utc>>,
+        history: Option<RunHistory>,
+    },
+    Flush(oneshot::Sender<()>),
+}
+
+// records what runs leave behind in the store from its own task, so a slow store doesn't hold up
+// dispatching. Writes queued while the previous batch was written go to the store together, with
+// only the latest state of each job.
+pub(crate) struct StoreWriter {
+    writes: mpsc::UnboundedSender<Write>,
+    task: JoinHandle<()>,
+}
+
+impl StoreWriter {
+    pub(crate) fn spawn(store: Arc<dyn JobStore>) -> Self {
+        let (writes, writes_rx) = mpsc::unbounded_channel();
+        Self {
+            writes,
+            task: tokio::spawn(write_batches(store, writes_rx)),
+        }
+    }
+
+    pub(crate) fn record(
+        &self,
+        job: &str,
+        last_run: Option<DateTime<Utc>>,
+        history: Option<RunHistory>,
+    ) {
+        let _ = self.writes.send(Write::Run {
+            job: job.to_string(),
+            last_run,
+            history,
+        });
+    }
+
+    // waits until everything recorded so far is written, before the job is changed in the store
+    pub(crate) async fn flush(&self) {
+        let (reply, written) = oneshot::channel();
+        if self.writes.send(Write::Flush(reply)).is_ok() {
+            let _ = written.await;
+        }
+    }
+
+    pub(crate) async fn close(self) {
+        drop(self.writes);
+        if let Err(error) = self.task.await {
+            error!(%error, "failed to record runs");
+        }
+    }
+}
+
+async fn write_batches(store: Arc<dyn JobStore>, mut writes: mpsc::UnboundedReceiver<Write>) {
+    while let Some(write) = writes.recv().await {
+        let mut last_runs = BTreeMap::new();
+        let mut histories = BTreeMap::new();
+        let mut flushed = Vec::new();
+        for write in std::iter::once(write).chain(std::iter::from_fn(|| writes.try_recv().ok())) {
+            match write {
+                Write::Run {
+                    job,
+                    last_run,
+                    history,
+                } => {
+                    // runs finish out of order, the latest fire is what counts
+                    if let Some(at) = last_run {
+                        let latest = last_runs.entry(job.clone()).or_insert(at);
+                        *latest = at.max(*latest);
+                    }
+                    if let Some(history) = history {
+                        histories.insert(job, history);
+                    }
+                }
+                Write::Flush(reply) => flushed.push(reply),
+            }
+        }
+        if !last_runs.is_empty() || !histories.is_empty() {
+            if let Err(error) = store.record_runs(&last_runs, &histories).await {
+                error!(%error, "failed to record runs");
+            }
+        }
+        for reply in flushed {
+            let _ = reply.send(());
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use super::{
+    histories_from_map, jobs_from_map, record_history_in_map, record_last_run_in_map,
+    record_paused_in_map, upsert_into_map, JobMap, JobStore, Result,
+};
+use crate::job::{Job, RunHistory};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use tokio::fs;
+use tokio::sync::Mutex;
+
+/// Keeps all jobs in a single JSON document, rewritten atomically on every change. The
+/// scheduler batches the changes runs make, so busy jobs don't rewrite it once per run.
+#[derive(Debug)]
+pub struct JsonFileStore {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl JsonFileStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    async fn read(&self) -> Result<JobMap> {
+        match fs::read(&self.path).await {
+            Ok(contents) => Ok(serde_json::from_slice(&contents)?),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(JobMap::new()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    async fn write(&self, jobs: &JobMap) -> Result<()> {
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        fs::write(&tmp_path, serde_json::to_vec_pretty(jobs)?).await?;
+        fs::rename(&tmp_path, &self.path).await?;
+        Ok(())
+    }
+
+    async fn update(&self, f: impl FnOnce(&mut JobMap) -> Result<()>) -> Result<()> {
+        let _lock = self.lock.lock().await;
+        let mut jobs = self.read().await?;
+        f(&mut jobs)?;
+        self.write(&jobs).await
+    }
+}
+
+#[async_trait]
+impl JobStore for JsonFileStore {
+    async fn load_all(&self) -> Result<Vec<Job>> {
+        let _lock = self.lock.lock().await;
+        jobs_from_map(&self.read().await?)
+    }
+
+    async fn upsert(&self, job: &Job) -> Result<()> {
+        self.update(|jobs| upsert_into_map(jobs, job)).await
+    }
+
+    async fn delete(&self, name: &str) -> Result<()> {
+        self.update(|jobs| {
+            jobs.remove(name);
+            Ok(())
+        })
+        .await
+    }
+
+    async fn record_last_run(&self, name: &str, at: DateTime<Utc>) -> Result<()> {
+        self.update(|jobs| record_last_run_in_map(jobs, name, at))
+            .await
+    }
+
+    async fn record_paused(&self, name: &str, paused: bool) -> Result<()> {
+        self.update(|jobs| record_paused_in_map(jobs, name, paused))
+            .await
+    }
+
+    async fn record_history(&self, name: &str, history: &RunHistory) -> Result<()> {
+        self.update(|jobs| record_history_in_map(jobs, name, history))
+            .await
+    }
+
+    // one rewrite of the file for the whole batch
+    async fn record_runs(
+        &self,
+        last_runs: &BTreeMap<String, DateTime<Utc>>,
+        histories: &BTreeMap<String, RunHistory>,
+    ) -> Result<()> {
+        self.update(|jobs| {
+            for (name, at) in last_runs {
+                record_last_run_in_map(jobs, name, *at)?;
+            }
+            for (name, history) in histories {
+                record_history_in_map(jobs, name, history)?;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    async fn load_histories(&self) -> Result<BTreeMap<String, RunHistory>> {
+        let _lock = self.lock.lock().await;
+        histories_from_map(&self.read().await?)
+    }
+}
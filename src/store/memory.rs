@@ -0,0 +1,53 @@
+use super::{
+    histories_from_map, jobs_from_map, record_history_in_map, record_last_run_in_map,
+    record_paused_in_map, upsert_into_map, JobMap, JobStore, Result,
+};
+use crate::job::{Job, RunHistory};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+#[derive(Default, Debug)]
+pub struct MemoryStore {
+    jobs: Mutex<JobMap>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl JobStore for MemoryStore {
+    async fn load_all(&self) -> Result<Vec<Job>> {
+        jobs_from_map(&self.jobs.lock().unwrap())
+    }
+
+    async fn upsert(&self, job: &Job) -> Result<()> {
+        upsert_into_map(&mut self.jobs.lock().unwrap(), job)
+    }
+
+    async fn delete(&self, name: &str) -> Result<()> {
+        self.jobs.lock().unwrap().remove(name);
+        Ok(())
+    }
+
+    async fn record_last_run(&self, name: &str, at: DateTime<Utc>) -> Result<()> {
+        record_last_run_in_map(&mut self.jobs.lock().unwrap(), name, at)
+    }
+
+    async fn record_paused(&self, name: &str, paused: bool) -> Result<()> {
+        record_paused_in_map(&mut self.jobs.lock().unwrap(), name, paused)
+    }
+
+    async fn record_history(&self, name: &str, history: &RunHistory) -> Result<()> {
+        record_history_in_map(&mut self.jobs.lock().unwrap(), name, history)
+    }
+
+    async fn load_histories(&self) -> Result<BTreeMap<String, RunHistory>> {
+        histories_from_map(&self.jobs.lock().unwrap())
+    }
+}
@@ -0,0 +1,110 @@
+pub mod json_file;
+pub mod memory;
+
+use crate::error::StoreError;
+use crate::job::{Job, RunHistory};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+pub type Result<T> = std::result::Result<T, StoreError>;
+
+#[async_trait]
+pub trait JobStore: Send + Sync {
+    async fn load_all(&self) -> Result<Vec<Job>>;
+
+    async fn upsert(&self, job: &Job) -> Result<()>;
+
+    async fn delete(&self, name: &str) -> Result<()>;
+
+    /// Records `at` as the job's last run unless a later one is stored, if the job is stored.
+    /// Runs can finish out of order, so an earlier fire may be reported last.
+    async fn record_last_run(&self, name: &str, at: DateTime<Utc>) -> Result<()>;
+
+    /// Records whether the job is paused, if the job is stored.
+    async fn record_paused(&self, name: &str, paused: bool) -> Result<()>;
+
+    /// Replaces the stored run history of the job, if the job is stored.
+    async fn record_history(&self, name: &str, history: &RunHistory) -> Result<()>;
+
+    /// Records a batch of finished runs at once: the latest scheduled time and the run history
+    /// of each job, by job name. Jobs that aren't stored are skipped.
+    async fn record_runs(
+        &self,
+        last_runs: &BTreeMap<String, DateTime<Utc>>,
+        histories: &BTreeMap<String, RunHistory>,
+    ) -> Result<()> {
+        for (name, at) in last_runs {
+            self.record_last_run(name, *at).await?;
+        }
+        for (name, history) in histories {
+            self.record_history(name, history).await?;
+        }
+        Ok(())
+    }
+
+    /// Run histories of the stored jobs that have one, by job name.
+    async fn load_histories(&self) -> Result<BTreeMap<String, RunHistory>>;
+}
+
+// serialized jobs keyed by name, shared by the backends that keep everything in one document
+type JobMap = BTreeMap<String, Value>;
+
+fn jobs_from_map(jobs: &JobMap) -> Result<Vec<Job>> {
+    jobs.values()
+        .map(|job| Ok(serde_json::from_value(job.clone())?))
+        .collect()
+}
+
+// the history is kept next to the job's definition and survives updates of it
+fn upsert_into_map(jobs: &mut JobMap, job: &Job) -> Result<()> {
+    let mut value = serde_json::to_value(job)?;
+    let history = jobs
+        .get_mut(&job.name)
+        .and_then(|stored| stored.as_object_mut()?.remove("history"));
+    if let (Value::Object(value), Some(history)) = (&mut value, history) {
+        value.insert("history".to_string(), history);
+    }
+    jobs.insert(job.name.clone(), value);
+    Ok(())
+}
+
+fn record_last_run_in_map(jobs: &mut JobMap, name: &str, at: DateTime<Utc>) -> Result<()> {
+    if let Some(Value::Object(job)) = jobs.get_mut(name) {
+        let stored = job
+            .get("last_run")
+            .and_then(|at| serde_json::from_value::<DateTime<Utc>>(at.clone()).ok());
+        if stored.is_none_or(|stored| stored < at) {
+            job.insert("last_run".to_string(), serde_json::to_value(at)?);
+        }
+    }
+    Ok(())
+}
+
+fn record_paused_in_map(jobs: &mut JobMap, name: &str, paused: bool) -> Result<()> {
+    if let Some(Value::Object(job)) = jobs.get_mut(name) {
+        match paused {
+            true => job.insert("paused".to_string(), Value::Bool(true)),
+            false => job.remove("paused"),
+        };
+    }
+    Ok(())
+}
+
+fn record_history_in_map(jobs: &mut JobMap, name: &str, history: &RunHistory) -> Result<()> {
+    if let Some(Value::Object(job)) = jobs.get_mut(name) {
+        job.insert("history".to_string(), serde_json::to_value(history)?);
+    }
+    Ok(())
+}
+
+fn histories_from_map(jobs: &JobMap) -> Result<BTreeMap<String, RunHistory>> {
+    jobs.iter()
+        .filter_map(|(name, job)| Some((name, job.get("history")?)))
+        .map(|(name, history)| Ok((name.clone(), serde_json::from_value(history.clone())?)))
+        .collect()
+}
+
+pub use self::{json_file::JsonFileStore, memory::MemoryStore};
@@ -0,0 +1,199 @@
+use crate::tests::{dt_parse, echo, fires, DEFAULT_UTC};
+
+use crate::clock::TokioClock;
+use crate::error::SchedulerError;
+use crate::job::Job;
+use crate::scheduler::Scheduler;
+use crate::trigger::{Calendar, Exclude, ExcludedRun, Exclusion, Interval, Trigger, Weekly};
+use crate::triggerSet;
+
+use chrono::{NaiveDate, NaiveTime};
+use chrono_tz::{Europe::Berlin, UTC};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
+
+fn time(hour: u32, minute: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+}
+
+#[test]
+fn calendar_exclusions() {
+    let calendar = Calendar::new(Berlin)
+        .with_exclusion(Exclusion::Annual(12, 25))
+        .with_exclusion(Exclusion::Date(date(2023, 5, 1)))
+        .with_exclusion(Exclusion::Range(date(2023, 8, 7), date(2023, 8, 11)))
+        .with_exclusion(Exclusion::Blackout(time(22, 0), time(6, 0)));
+
+    assert!(calendar.excludes(dt_parse("2024-12-25T12:00:00+01:00")));
+    assert!(calendar.excludes(dt_parse("2023-05-01T00:00:00+02:00")));
+    assert!(!calendar.excludes(dt_parse("2023-05-02T12:00:00+02:00")));
+    assert!(calendar.excludes(dt_parse("2023-08-09T12:00:00+02:00")));
+    assert!(calendar.excludes(dt_parse("2023-06-01T23:00:00+02:00")));
+    assert!(calendar.excludes(dt_parse("2023-06-02T05:59:00+02:00")));
+    assert!(!calendar.excludes(dt_parse("2023-06-02T06:00:00+02:00")));
+
+    // the night before a holiday runs into it, and it into the next night
+    assert_eq!(
+        calendar.next_allowed(dt_parse("2023-04-30T23:00:00+02:00")),
+        Some(dt_parse("2023-05-02T06:00:00+02:00"))
+    );
+    assert_eq!(
+        calendar.next_allowed(dt_parse("2023-08-06T22:30:00+02:00")),
+        Some(dt_parse("2023-08-12T06:00:00+02:00"))
+    );
+    let always = Calendar::new(UTC).with_exclusion(Exclusion::Blackout(time(0, 0), time(0, 0)));
+    assert_eq!(always.next_allowed(dt_parse(DEFAULT_UTC)), None);
+}
+
+#[test]
+fn exclude_skips_or_shifts() {
+    let calendar = Arc::new(
+        Calendar::new(UTC)
+            .with_exclusion(Exclusion::Date(date(2023, 1, 2)))
+            .with_exclusion(Exclusion::Blackout(time(12, 0), time(14, 0))),
+    );
+    let hourly = || Interval::new(Duration::from_secs(3600)).with_anchor(dt_parse(DEFAULT_UTC));
+
+    let skipping = Exclude::new(hourly(), "maintenance").with_calendar(calendar.clone());
+    assert_eq!(
+        fires(&skipping, "2023-01-01T10:30:00Z", 4),
+        [
+            "2023-01-01T11:00:00+00:00",
+            "2023-01-01T14:00:00+00:00",
+            "2023-01-01T15:00:00+00:00",
+            "2023-01-01T16:00:00+00:00",
+        ]
+    );
+    assert_eq!(
+        fires(&skipping, "2023-01-01T22:30:00Z", 2),
+        ["2023-01-01T23:00:00+00:00", "2023-01-03T00:00:00+00:00"]
+    );
+
+    let shifting = Exclude::new(
+        hourly().with_anchor(dt_parse("2023-01-01T00:30:00Z")),
+        "maintenance",
+    )
+    .with_excluded_run(ExcludedRun::Shift)
+    .with_calendar(calendar);
+    assert_eq!(
+        fires(&shifting, "2023-01-01T10:00:00Z", 4),
+        [
+            "2023-01-01T10:30:00+00:00",
+            "2023-01-01T11:30:00+00:00",
+            "2023-01-01T14:00:00+00:00",
+            "2023-01-01T14:30:00+00:00",
+        ]
+    );
+
+    let unbound = Exclude::new(hourly(), "maintenance");
+    assert_eq!(unbound.next_after(dt_parse(DEFAULT_UTC)), None);
+    assert_eq!(
+        serde_json::to_string(&(Box::new(unbound) as Box<dyn Trigger>)).unwrap(),
+        r#"{"type":"Exclude","inner":{"type":"Interval","interval":{"secs":3600,"nanos":0},"last_run":"2023-01-01T00:00:00Z"},"calendar":"maintenance"}"#
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn share_calendars_by_name() {
+    let clock = TokioClock::new(dt_parse(DEFAULT_UTC));
+    let holidays = || {
+        Calendar::new(UTC)
+            .with_exclusion(Exclusion::Annual(1, 1))
+            .with_exclusion(Exclusion::Date(date(2023, 1, 2)))
+    };
+    let nine = Duration::from_secs(9 * 3600);
+    let daily = || Weekly::new([true; 7], nine, UTC);
+    let excluding = |name: &str, calendar: &str| {
+        Job::new_fallible(
+            name.to_string(),
+            echo,
+            Value::Null,
+            triggerSet![Exclude::new(daily(), calendar)],
+        )
+    };
+    let mut scheduler = Scheduler::new().with_clock(clock);
+    for name in ["report", "backup"] {
+        scheduler.add_job(excluding(name, "holidays")).unwrap();
+    }
+    // registered after the jobs that refer to it
+    let scheduler = scheduler.with_calendar("holidays", holidays());
+    let handle = scheduler.handle();
+    let scheduler = tokio::spawn(scheduler.run());
+
+    assert_eq!(
+        handle.add_job(excluding("cleanup", "weekends")).await,
+        Err(SchedulerError::UnknownCalendar("weekends".to_string()))
+    );
+    let other = Calendar::new(UTC).with_exclusion(Exclusion::Annual(12, 25));
+    assert_eq!(
+        handle
+            .add_job(Job::new_fallible(
+                "audit".to_string(),
+                echo,
+                Value::Null,
+                triggerSet![Exclude::new(daily(), "holidays").with_calendar(Arc::new(other))],
+            ))
+            .await,
+        Err(SchedulerError::ConflictingCalendar("holidays".to_string()))
+    );
+    handle
+        .add_job(Job::new_fallible(
+            "archive".to_string(),
+            echo,
+            Value::Null,
+            triggerSet![Exclude::new(daily(), "holidays").with_calendar(Arc::new(holidays()))],
+        ))
+        .await
+        .unwrap();
+
+    let jobs = handle.jobs().await.unwrap();
+    assert_eq!(jobs.len(), 3);
+    for job in jobs {
+        assert_eq!(job.next_run, Some(dt_parse("2023-01-03T09:00:00Z")));
+    }
+
+    handle.shutdown(Duration::from_secs(1)).await.unwrap();
+    scheduler.await.unwrap();
+}
+
+#[test]
+fn preview_excluded_fires() {
+    let holidays = Calendar::new(UTC)
+        .with_exclusion(Exclusion::Annual(1, 1))
+        .with_exclusion(Exclusion::Date(date(2023, 1, 2)));
+    let daily = || Weekly::new([true; 7], Duration::from_secs(9 * 3600), UTC);
+    let mut scheduler = Scheduler::new();
+    for (name, calendar) in [("report", "holidays"), ("cleanup", "weekends")] {
+        scheduler
+            .add_job(Job::new_fallible(
+                name.to_string(),
+                echo,
+                Value::Null,
+                triggerSet![Exclude::new(daily(), calendar)],
+            ))
+            .unwrap();
+    }
+    let scheduler = scheduler.with_calendar("holidays", holidays);
+
+    let fires: Vec<(&str, String)> = scheduler
+        .preview(
+            dt_parse("2023-01-01T00:00:00Z"),
+            dt_parse("2023-01-05T12:00:00Z"),
+        )
+        .into_iter()
+        .map(|fire| (fire.job, fire.at.to_rfc3339()))
+        .collect();
+    assert_eq!(
+        fires,
+        [
+            ("report", "2023-01-03T09:00:00+00:00".to_string()),
+            ("report", "2023-01-04T09:00:00+00:00".to_string()),
+            ("report", "2023-01-05T09:00:00+00:00".to_string()),
+        ]
+    );
+}
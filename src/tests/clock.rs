@@ -0,0 +1,89 @@
+use crate::tests::{dt_parse, DEFAULT_UTC};
+
+use crate::clock::{Clock, FixedClock, ManualClock, TokioClock};
+use crate::job::Job;
+use crate::scheduler::Scheduler;
+use crate::trigger::{Cron, Oneshot};
+use crate::triggerSet;
+use chrono::{DateTime, Utc};
+use chrono_tz::UTC;
+use serde_json::Value;
+use std::time::Duration;
+
+#[tokio::test]
+async fn fixed_clock_only_sleeps_into_the_past() {
+    let clock = FixedClock::new(dt_parse(DEFAULT_UTC));
+    clock.sleep_until(dt_parse(DEFAULT_UTC)).await;
+
+    let sleep = clock.sleep_until(dt_parse("2023-01-01T00:00:01Z"));
+    assert!(tokio::time::timeout(Duration::from_millis(50), sleep)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn manual_clock_wakes_sleepers_when_advanced() {
+    let clock = ManualClock::new(dt_parse(DEFAULT_UTC));
+    let sleep = tokio::spawn(clock.sleep_until(dt_parse("2023-01-01T01:00:00Z")));
+
+    clock.advance(Duration::from_secs(1800));
+    tokio::task::yield_now().await;
+    assert!(!sleep.is_finished());
+
+    clock.advance(Duration::from_secs(1800));
+    sleep.await.unwrap();
+    assert_eq!(clock.now(), dt_parse("2023-01-01T01:00:00Z"));
+}
+
+#[tokio::test]
+async fn scheduler_follows_manual_clock() {
+    let clock = ManualClock::new(dt_parse(DEFAULT_UTC));
+    let mut scheduler = Scheduler::new().with_clock(clock.clone());
+    scheduler
+        .add_job(Job::new(
+            "hourly".to_string(),
+            None,
+            Value::Null,
+            triggerSet![Oneshot::new(dt_parse("2023-01-01T01:00:00Z"))],
+        ))
+        .unwrap();
+    let mut results = scheduler.subscribe();
+    let scheduler = tokio::spawn(scheduler.run());
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(results.try_recv().is_err());
+
+    clock.set(dt_parse("2023-01-01T01:00:00Z"));
+    scheduler.await.unwrap();
+    let record = results.try_recv().unwrap();
+    assert_eq!(record.started, dt_parse("2023-01-01T01:00:00Z"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn fast_forward_a_week() {
+    let mut scheduler = Scheduler::new().with_clock(TokioClock::new(dt_parse(DEFAULT_UTC)));
+    scheduler
+        .add_job(Job::new(
+            "daily".to_string(),
+            None,
+            Value::Null,
+            triggerSet![Cron::new("0 12 * * *", UTC).unwrap()],
+        ))
+        .unwrap();
+    let handle = scheduler.handle();
+    let mut results = scheduler.subscribe();
+    let scheduler = tokio::spawn(scheduler.run());
+
+    tokio::time::sleep(Duration::from_secs(7 * 24 * 3600)).await;
+    handle.shutdown(Duration::from_secs(1)).await.unwrap();
+    scheduler.await.unwrap();
+
+    let mut runs: Vec<DateTime<Utc>> = Vec::new();
+    while let Ok(record) = results.try_recv() {
+        runs.push(record.scheduled);
+    }
+    let expected: Vec<DateTime<Utc>> = (1..=7)
+        .map(|day| dt_parse(&format!("2023-01-0{}T12:00:00Z", day)))
+        .collect();
+    assert_eq!(runs, expected);
+}
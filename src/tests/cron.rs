@@ -0,0 +1,301 @@
+use crate::tests::{dt_parse, DEFAULT_UTC, DST_SPRING_LOCAL};
+
+use crate::clock::FixedClock;
+use crate::job::Job;
+use crate::trigger::{Cron, DstFold, DstGap, Trigger};
+use crate::triggerSet;
+use chrono::{DateTime, Utc};
+use chrono_tz::{Europe::Berlin, UTC};
+use serde_json::Value;
+
+fn parse_all(dts: &[&str]) -> Vec<DateTime<Utc>> {
+    dts.iter().map(|dt| dt_parse(dt)).collect()
+}
+
+#[test]
+fn every_15_minutes_on_weekday_business_hours() {
+    let clock = FixedClock::new(dt_parse(DEFAULT_UTC));
+    let cron = Cron::new("*/15 8-18 * * MON-FRI", UTC).unwrap();
+    let next_runs = cron.next_runs(&clock, 46).unwrap();
+
+    assert_eq!(
+        next_runs[..3],
+        parse_all(&[
+            "2023-01-02T08:00:00Z",
+            "2023-01-02T08:15:00Z",
+            "2023-01-02T08:30:00Z",
+        ])
+    );
+    assert_eq!(
+        next_runs[43..],
+        parse_all(&[
+            "2023-01-02T18:45:00Z",
+            "2023-01-03T08:00:00Z",
+            "2023-01-03T08:15:00Z",
+        ])
+    );
+}
+
+#[test]
+fn six_fields_with_seconds_in_timezone() {
+    let clock = FixedClock::new(dt_parse(DEFAULT_UTC));
+    let cron = Cron::new("30 0 9 * * *", Berlin).unwrap();
+
+    assert_eq!(
+        cron.next_runs(&clock, 2).unwrap(),
+        parse_all(&["2023-01-01T09:00:30+01:00", "2023-01-02T09:00:30+01:00"])
+    );
+}
+
+#[test]
+fn month_names_and_lists() {
+    let clock = FixedClock::new(dt_parse(DEFAULT_UTC));
+    let cron = Cron::new("0 0 1 JAN,jul ?", UTC).unwrap();
+
+    assert_eq!(
+        cron.next_runs(&clock, 2).unwrap(),
+        parse_all(&["2023-07-01T00:00:00Z", "2024-01-01T00:00:00Z"])
+    );
+}
+
+#[test]
+fn last_day_of_month() {
+    let clock = FixedClock::new(dt_parse(DEFAULT_UTC));
+    let last = Cron::new("0 0 L * *", UTC).unwrap();
+    let last_weekday = Cron::new("0 0 LW 1-4 *", UTC).unwrap();
+
+    assert_eq!(
+        last.next_runs(&clock, 3).unwrap(),
+        parse_all(&[
+            "2023-01-31T00:00:00Z",
+            "2023-02-28T00:00:00Z",
+            "2023-03-31T00:00:00Z",
+        ])
+    );
+    assert_eq!(
+        last_weekday.next_runs(&clock, 4).unwrap(),
+        parse_all(&[
+            "2023-01-31T00:00:00Z",
+            "2023-02-28T00:00:00Z",
+            "2023-03-31T00:00:00Z",
+            "2023-04-28T00:00:00Z",
+        ])
+    );
+}
+
+#[test]
+fn nearest_weekday() {
+    let clock = FixedClock::new(dt_parse(DEFAULT_UTC));
+    let cron = Cron::new("0 0 15W 1-4 *", UTC).unwrap();
+
+    assert_eq!(
+        cron.next_runs(&clock, 4).unwrap(),
+        parse_all(&[
+            "2023-01-16T00:00:00Z",
+            "2023-02-15T00:00:00Z",
+            "2023-03-15T00:00:00Z",
+            "2023-04-14T00:00:00Z",
+        ])
+    );
+}
+
+#[test]
+fn nth_and_last_weekday_of_month() {
+    let clock = FixedClock::new(dt_parse(DEFAULT_UTC));
+    let second_friday = Cron::new("0 0 * * FRI#2", UTC).unwrap();
+    let last_friday = Cron::new("0 0 * * 5L", UTC).unwrap();
+
+    assert_eq!(
+        second_friday.next_runs(&clock, 3).unwrap(),
+        parse_all(&[
+            "2023-01-13T00:00:00Z",
+            "2023-02-10T00:00:00Z",
+            "2023-03-10T00:00:00Z",
+        ])
+    );
+    assert_eq!(
+        last_friday.next_runs(&clock, 3).unwrap(),
+        parse_all(&[
+            "2023-01-27T00:00:00Z",
+            "2023-02-24T00:00:00Z",
+            "2023-03-31T00:00:00Z",
+        ])
+    );
+}
+
+#[test]
+fn steps_in_day_fields() {
+    let clock = FixedClock::new(dt_parse(DEFAULT_UTC));
+    // 2023-01-01 is a Sunday
+    let odd_days = Cron::new("0 0 */2 * *", UTC).unwrap();
+    assert_eq!(
+        odd_days.next_runs(&clock, 3).unwrap(),
+        parse_all(&[
+            "2023-01-03T00:00:00Z",
+            "2023-01-05T00:00:00Z",
+            "2023-01-07T00:00:00Z",
+        ])
+    );
+
+    let every_other_weekday = Cron::new("0 0 * * */2", UTC).unwrap();
+    assert_eq!(
+        every_other_weekday.next_runs(&clock, 4).unwrap(),
+        parse_all(&[
+            "2023-01-03T00:00:00Z",
+            "2023-01-05T00:00:00Z",
+            "2023-01-07T00:00:00Z",
+            "2023-01-08T00:00:00Z",
+        ])
+    );
+
+    // a restricted field that starts with `*` still narrows down the other one
+    let odd_mondays = Cron::new("0 0 */2 * MON", UTC).unwrap();
+    assert_eq!(
+        odd_mondays.next_runs(&clock, 2).unwrap(),
+        parse_all(&["2023-01-09T00:00:00Z", "2023-01-23T00:00:00Z"])
+    );
+}
+
+#[test]
+fn nonexistent_local_time() {
+    let clock = FixedClock::new(dt_parse(DST_SPRING_LOCAL));
+    let cron = Cron::new("30 2 * * *", Berlin).unwrap();
+
+    assert_eq!(
+        cron.next_runs(&clock, 3).unwrap(),
+        parse_all(&[
+            "2023-03-24T02:30:00+01:00",
+            "2023-03-25T02:30:00+01:00",
+            "2023-03-26T03:30:00+02:00",
+        ])
+    );
+    assert_eq!(
+        cron.clone()
+            .with_dst_gap(DstGap::Skip)
+            .next_runs(&clock, 3)
+            .unwrap(),
+        parse_all(&[
+            "2023-03-24T02:30:00+01:00",
+            "2023-03-25T02:30:00+01:00",
+            "2023-03-27T02:30:00+02:00",
+        ])
+    );
+    assert_eq!(
+        cron.with_dst_gap(DstGap::Transition)
+            .next_runs(&clock, 3)
+            .unwrap()[2],
+        dt_parse("2023-03-26T03:00:00+02:00")
+    );
+
+    // times in the gap don't fire on top of the ones right after it
+    let quarter_hours = Cron::new("*/15 * * * *", Berlin).unwrap();
+    let clock = FixedClock::new(dt_parse("2023-03-26T00:30:00Z"));
+    assert_eq!(
+        quarter_hours.next_runs(&clock, 4).unwrap(),
+        parse_all(&[
+            "2023-03-26T00:45:00Z",
+            "2023-03-26T01:00:00Z",
+            "2023-03-26T01:15:00Z",
+            "2023-03-26T01:30:00Z",
+        ])
+    );
+}
+
+#[test]
+fn repeated_local_time() {
+    // Berlin falls back from 03:00 to 02:00 on 2023-10-29, at 01:00Z
+    let clock = FixedClock::new(dt_parse("2023-10-29T00:30:00Z"));
+    let quarter_hours = Cron::new("*/15 * * * *", Berlin).unwrap();
+    let runs = |cron: Cron, n| cron.next_runs(&clock, n).unwrap();
+
+    assert_eq!(
+        runs(quarter_hours.clone(), 3),
+        parse_all(&[
+            "2023-10-29T00:45:00Z",
+            "2023-10-29T02:00:00Z",
+            "2023-10-29T02:15:00Z",
+        ])
+    );
+    assert_eq!(
+        runs(quarter_hours.clone().with_dst_fold(DstFold::Both), 7),
+        parse_all(&[
+            "2023-10-29T00:45:00Z",
+            "2023-10-29T01:00:00Z",
+            "2023-10-29T01:15:00Z",
+            "2023-10-29T01:30:00Z",
+            "2023-10-29T01:45:00Z",
+            "2023-10-29T02:00:00Z",
+            "2023-10-29T02:15:00Z",
+        ])
+    );
+    assert_eq!(
+        runs(quarter_hours.with_dst_fold(DstFold::Second), 3),
+        parse_all(&[
+            "2023-10-29T01:00:00Z",
+            "2023-10-29T01:15:00Z",
+            "2023-10-29T01:30:00Z",
+        ])
+    );
+
+    let daily = Cron::new("30 2 * * *", Berlin)
+        .unwrap()
+        .with_dst_fold(DstFold::Both);
+    let clock = FixedClock::new(dt_parse("2023-10-28T12:00:00Z"));
+    assert_eq!(
+        daily.next_runs(&clock, 3).unwrap(),
+        parse_all(&[
+            "2023-10-29T00:30:00Z",
+            "2023-10-29T01:30:00Z",
+            "2023-10-30T01:30:00Z",
+        ])
+    );
+    let json = serde_json::to_string(&daily).unwrap();
+    assert_eq!(
+        json,
+        r#"{"expression":"30 2 * * *","tz":"Europe/Berlin","dst_fold":"Both"}"#
+    );
+    assert_eq!(
+        serde_json::from_str::<Cron>(&json)
+            .unwrap()
+            .next_runs(&clock, 3)
+            .unwrap(),
+        daily.next_runs(&clock, 3).unwrap()
+    );
+}
+
+#[test]
+fn impossible_date() {
+    let clock = FixedClock::new(dt_parse(DEFAULT_UTC));
+    let cron = Cron::new("0 0 30 2 *", UTC).unwrap();
+
+    assert_eq!(cron.next_runs(&clock, 1), None);
+}
+
+#[test]
+fn invalid_expressions() {
+    for expression in [
+        "* * *",
+        "60 * * * *",
+        "* * * * MON#6",
+        "5-1 * * * *",
+        "*/0 * * * *",
+        "* * * FOO *",
+    ] {
+        assert!(Cron::new(expression, UTC).is_err(), "{}", expression);
+    }
+}
+
+#[test]
+fn cron_job_serde_round_trip() {
+    let cron = Cron::new("0 */15 8-18 * * MON-FRI", Berlin).unwrap();
+    let job = Job::new("test".to_string(), None, Value::Null, triggerSet![cron]);
+
+    let expected_job_json = r#"{"name":"test","callback_context":null,"triggers":[{"type":"Cron","expression":"0 */15 8-18 * * MON-FRI","tz":"Europe/Berlin"}]}"#;
+
+    let job_json = serde_json::to_string(&job).unwrap();
+    assert_eq!(expected_job_json, job_json);
+    assert_eq!(job, serde_json::from_str::<Job>(&job_json).unwrap());
+
+    let invalid_json = r#"{"name":"test","callback_context":null,"triggers":[{"type":"Cron","expression":"0 0 32 * *","tz":"UTC"}]}"#;
+    assert!(serde_json::from_str::<Job>(invalid_json).is_err());
+}
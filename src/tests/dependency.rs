@@ -0,0 +1,122 @@
+use crate::tests::{dt_parse, DEFAULT_UTC};
+
+use crate::clock::TokioClock;
+use crate::error::{JobError, SchedulerError};
+use crate::job::{Job, Result};
+use crate::scheduler::Scheduler;
+use crate::trigger::{Condition, Dependency, Oneshot};
+use crate::triggerSet;
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+
+fn succeeding(_context: &Value) -> Result<Value> {
+    Ok(Value::Null)
+}
+
+fn failing(_context: &Value) -> Result<Value> {
+    Err(JobError::failed("disk full"))
+}
+
+fn cancelled(_context: &Value) -> Result<Value> {
+    Err(JobError::Cancelled)
+}
+
+#[tokio::test]
+async fn run_jobs_after_their_dependencies() {
+    let clock = TokioClock::new(dt_parse(DEFAULT_UTC));
+    let fire = dt_parse(DEFAULT_UTC) + Duration::from_millis(100);
+    let job = |name: &str, callback: fn(&Value) -> Result<Value>, trigger| {
+        Job::new_fallible(name.to_string(), callback, Value::Null, trigger)
+    };
+
+    let mut scheduler = Scheduler::new().with_clock(clock);
+    for job in [
+        job("aggregate", succeeding, triggerSet![Oneshot::new(fire)]),
+        job("cleanup", failing, triggerSet![Oneshot::new(fire)]),
+        job("sync", cancelled, triggerSet![Oneshot::new(fire)]),
+        job(
+            "export",
+            succeeding,
+            triggerSet![Dependency::after("aggregate")],
+        ),
+        job(
+            "report",
+            succeeding,
+            triggerSet![Dependency::new(["aggregate", "export"], Condition::Success)],
+        ),
+        job(
+            "alert",
+            succeeding,
+            triggerSet![Dependency::new(["cleanup"], Condition::Failure)],
+        ),
+        // a cancelled run isn't a failure
+        job(
+            "page",
+            succeeding,
+            triggerSet![Dependency::new(["sync"], Condition::Failure)],
+        ),
+        job(
+            "compact",
+            succeeding,
+            triggerSet![Dependency::after("cleanup")],
+        ),
+    ] {
+        scheduler.add_job(job).unwrap();
+    }
+    let mut results = scheduler.subscribe();
+    scheduler.run().await;
+
+    let mut records = HashMap::new();
+    while let Ok(record) = results.try_recv() {
+        records.insert(record.job.clone(), record);
+    }
+    let mut names: Vec<_> = records.keys().cloned().collect();
+    names.sort();
+    assert_eq!(
+        names,
+        ["aggregate", "alert", "cleanup", "export", "report", "sync"]
+    );
+    assert!(records["export"].started >= records["aggregate"].finished());
+    assert!(records["report"].started >= records["export"].finished());
+    assert_eq!(
+        records["export"].trigger,
+        Some(json!({ "type": "Dependency", "jobs": ["aggregate"], "condition": "Success" }))
+    );
+}
+
+#[test]
+fn reject_dependency_cycles() {
+    let dependent = |name: &str, upstream: &str| {
+        Job::new(
+            name.to_string(),
+            None,
+            Value::Null,
+            triggerSet![Dependency::after(upstream)],
+        )
+    };
+    let mut scheduler = Scheduler::new();
+    scheduler.add_job(dependent("b", "a")).unwrap();
+    scheduler.add_job(dependent("c", "b")).unwrap();
+
+    let error = scheduler.add_job(dependent("a", "c")).unwrap_err();
+    assert_eq!(
+        error,
+        SchedulerError::DependencyCycle(vec![
+            "a".to_string(),
+            "c".to_string(),
+            "b".to_string(),
+            "a".to_string(),
+        ])
+    );
+    assert_eq!(error.to_string(), "dependency cycle: a -> c -> b -> a");
+    assert_eq!(
+        scheduler.add_job(dependent("self", "self")),
+        Err(SchedulerError::DependencyCycle(vec![
+            "self".to_string(),
+            "self".to_string(),
+        ]))
+    );
+    scheduler.add_job(dependent("d", "c")).unwrap();
+}
@@ -0,0 +1,64 @@
+use crate::tests::{dt_parse, echo, DEFAULT_UTC};
+
+use crate::clock::TokioClock;
+use crate::job::Job;
+use crate::scheduler::Scheduler;
+use crate::trigger::{Event, Interval};
+use crate::triggerSet;
+
+use serde_json::{json, Value};
+use std::time::Duration;
+
+#[tokio::test(start_paused = true)]
+async fn fire_jobs_on_emitted_events() {
+    let clock = TokioClock::new(dt_parse(DEFAULT_UTC));
+    let hourly = Duration::from_secs(3600);
+    let mut scheduler = Scheduler::new().with_clock(clock);
+    scheduler
+        .add_job(Job::new_fallible(
+            "notify".to_string(),
+            echo,
+            json!({ "channel": "ops", "version": null }),
+            triggerSet![Event::new("deployed"), Interval::new(hourly)],
+        ))
+        .unwrap();
+    scheduler
+        .add_job(Job::new_fallible(
+            "paused".to_string(),
+            echo,
+            Value::Null,
+            triggerSet![Event::new("deployed")],
+        ))
+        .unwrap();
+    let handle = scheduler.handle();
+    let mut results = scheduler.subscribe();
+    let scheduler = tokio::spawn(scheduler.run());
+
+    handle.pause_job("paused").await.unwrap();
+    assert_eq!(
+        handle
+            .emit("deployed", json!({ "version": "1.2.0" }))
+            .await
+            .unwrap(),
+        vec!["notify".to_string()]
+    );
+    assert_eq!(
+        handle.emit("rolled-back", Value::Null).await.unwrap(),
+        Vec::<String>::new()
+    );
+
+    let record = results.recv().await.unwrap();
+    assert_eq!(
+        record.outcome,
+        Ok(json!({ "channel": "ops", "version": "1.2.0" }))
+    );
+    assert_eq!(
+        record.trigger,
+        Some(json!({ "type": "Event", "name": "deployed" }))
+    );
+    let jobs = handle.jobs().await.unwrap();
+    assert_eq!(jobs[0].next_run, Some(dt_parse(DEFAULT_UTC) + hourly));
+
+    handle.shutdown(Duration::from_secs(1)).await.unwrap();
+    scheduler.await.unwrap();
+}
@@ -0,0 +1,104 @@
+use crate::tests::{dt_parse, DEFAULT_UTC};
+
+use crate::clock::TokioClock;
+use crate::error::{JobError, SchedulerError};
+use crate::job::{Job, JobStats, RunHistory, RunRecord};
+use crate::scheduler::Scheduler;
+use crate::trigger::Interval;
+use crate::triggerSet;
+
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn record(millis: u64, outcome: Result<Value, JobError>) -> RunRecord {
+    let started = dt_parse(DEFAULT_UTC) + Duration::from_secs(millis);
+    RunRecord {
+        job: "job".to_string(),
+        scheduled: started,
+        started,
+        duration: Duration::from_millis(millis),
+        outcome,
+        attempt: 1,
+        manual: false,
+        trigger: None,
+    }
+}
+
+#[test]
+fn history_keeps_latest_runs() {
+    let mut history = RunHistory::new(100);
+    for millis in 1..=200 {
+        let outcome = match millis % 50 {
+            0 => Err(JobError::failed("flaky")),
+            _ => Ok(Value::Null),
+        };
+        history.push(record(millis, outcome));
+    }
+
+    assert_eq!(history.records().count(), 100);
+    assert_eq!(history.records().next().unwrap().duration.as_millis(), 101);
+    assert_eq!(
+        history.stats(),
+        JobStats {
+            runs: 200,
+            failures: 4,
+            p50: Some(Duration::from_millis(150)),
+            p99: Some(Duration::from_millis(199)),
+            last_success: Some(dt_parse(DEFAULT_UTC) + Duration::from_secs(199)),
+        }
+    );
+
+    history.set_capacity(10);
+    assert_eq!(history.records().count(), 10);
+    assert_eq!(history.stats().p50, Some(Duration::from_millis(195)));
+    assert_eq!(RunHistory::new(10).stats(), JobStats::default());
+}
+
+#[tokio::test(start_paused = true)]
+async fn query_history_through_handle() {
+    let clock = TokioClock::new(dt_parse(DEFAULT_UTC));
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut scheduler = Scheduler::new().with_clock(clock).with_history_size(3);
+    scheduler
+        .add_job(Job::new_async(
+            "flaky".to_string(),
+            move |_| {
+                let call = calls.fetch_add(1, Ordering::SeqCst) + 1;
+                async move {
+                    match call {
+                        2 => Err(JobError::failed("flaky")),
+                        call => Ok(json!(call)),
+                    }
+                }
+            },
+            Value::Null,
+            triggerSet![Interval::new(Duration::from_secs(1))],
+        ))
+        .unwrap();
+    let handle = scheduler.handle();
+    let mut results = scheduler.subscribe();
+    let scheduler = tokio::spawn(scheduler.run());
+
+    for _ in 0..5 {
+        results.recv().await.unwrap();
+    }
+    let history = handle.history("flaky").await.unwrap();
+    let outcomes: Vec<_> = history.iter().map(|run| run.outcome.clone()).collect();
+    assert_eq!(outcomes, vec![Ok(json!(3)), Ok(json!(4)), Ok(json!(5))]);
+    assert_eq!(
+        history[0].trigger.as_ref().unwrap()["type"],
+        json!("Interval")
+    );
+    let stats = handle.stats("flaky").await.unwrap();
+    assert_eq!((stats.runs, stats.failures), (5, 1));
+    assert_eq!(stats.last_success, Some(history[2].started));
+    assert_eq!(
+        handle.stats("unknown").await,
+        Err(SchedulerError::UnknownJob("unknown".to_string()))
+    );
+
+    handle.shutdown(Duration::from_secs(1)).await.unwrap();
+    scheduler.await.unwrap();
+}
@@ -1,22 +1,27 @@
-use crate::tests::fake_time::{dt_parse, set_start_time};
-use crate::tests::DEFAULT_UTC;
+use crate::tests::{dt_parse, DEFAULT_UTC};
 
-use crate::job::Job;
-use crate::trigger::{Interval, Oneshot, Weekly};
+use crate::clock::{SystemClock, TokioClock};
+use crate::error::{JobError, SchedulerError};
+use crate::job::{Callback, CallbackRegistry, Job};
+use crate::scheduler::Scheduler;
+use crate::trigger::{Interval, Limit, Oneshot, Weekly};
 use crate::triggerSet;
 use chrono::{DateTime, Utc};
 use chrono_tz::UTC;
 use serde_json::{json, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
 
-fn callback(context: &Value) {
+fn callback(_context: &Value) {
     println!("test job callback");
 }
 
 #[tokio::test]
 async fn test_job_run() {
-    set_start_time(DEFAULT_UTC);
+    let clock = TokioClock::new(dt_parse(DEFAULT_UTC));
     let oneshot = Oneshot::new(dt_parse(DEFAULT_UTC) + std::time::Duration::from_secs(1));
     let tc = triggerSet![oneshot];
     let cb_context = json!({
@@ -37,7 +42,7 @@ async fn test_job_run() {
 
     let mut join_set = JoinSet::new();
 
-    Job::run(job, &mut join_set);
+    Job::run_with_clock(job, Arc::new(clock), &mut join_set);
 
     join_set.join_next().await;
 }
@@ -99,3 +104,186 @@ async fn test_job_deserialize() {
 
     assert_eq!(expected_job, job);
 }
+
+#[tokio::test]
+async fn test_job_run_async() {
+    let clock = TokioClock::new(dt_parse(DEFAULT_UTC));
+    let oneshot = Oneshot::new(dt_parse(DEFAULT_UTC) + std::time::Duration::from_millis(100));
+    let runs = Arc::new(AtomicUsize::new(0));
+    let job = Job::new_async(
+        "test".to_string(),
+        {
+            let runs = runs.clone();
+            move |context: Value| {
+                let runs = runs.clone();
+                async move {
+                    assert_eq!(context, json!({"bar": 42}));
+                    tokio::task::yield_now().await;
+                    runs.fetch_add(1, Ordering::SeqCst);
+                    Ok(Value::Null)
+                }
+            }
+        },
+        json!({"bar": 42}),
+        triggerSet![oneshot],
+    );
+
+    let mut join_set = JoinSet::new();
+
+    Job::run_with_clock(job, Arc::new(clock), &mut join_set);
+
+    join_set.join_next().await;
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+}
+
+fn report(context: &Value) -> crate::job::Result<Value> {
+    Ok(json!({ "reported": context }))
+}
+
+fn registry() -> CallbackRegistry {
+    let mut registry = CallbackRegistry::new();
+    registry
+        .register("report", Callback::Fallible(report))
+        .register("noop", Callback::Blocking(callback));
+    registry
+}
+
+#[tokio::test]
+async fn test_registered_job_round_trip() {
+    let registry = registry();
+    let mut job = Job::new_registered(
+        "test".to_string(),
+        "report",
+        json!(1),
+        triggerSet![Interval::new(Duration::from_secs(1))],
+    );
+    assert!(!job.is_resolved());
+    registry.resolve(&mut job).unwrap();
+    assert!(job.is_resolved());
+
+    let job_json = serde_json::to_string(&job).unwrap();
+    assert_eq!(
+        job_json,
+        r#"{"name":"test","callback":"report","callback_context":1,"triggers":[{"type":"Interval","interval":{"secs":1,"nanos":0},"last_run":null}]}"#
+    );
+
+    let plain: Job = serde_json::from_str(&job_json).unwrap();
+    assert!(!plain.is_resolved());
+    assert_eq!(
+        plain
+            .execute(
+                &SystemClock,
+                DateTime::<Utc>::default(),
+                None,
+                CancellationToken::new(),
+                &CancellationToken::new(),
+            )
+            .await
+            .outcome,
+        Err(JobError::failed("callback \"report\" was never resolved"))
+    );
+
+    let resolved = registry.job_from_json(&job_json).unwrap();
+    assert_eq!(resolved, job);
+    assert_eq!(
+        resolved
+            .execute(
+                &SystemClock,
+                DateTime::<Utc>::default(),
+                None,
+                CancellationToken::new(),
+                &CancellationToken::new(),
+            )
+            .await
+            .outcome,
+        Ok(json!({ "reported": 1 }))
+    );
+}
+
+#[tokio::test]
+async fn test_unknown_registered_callback() {
+    let job_json = r#"{"name":"test","callback":"missing","callback_context":null,"triggers":[]}"#;
+    let error = registry().job_from_json(job_json).unwrap_err();
+    assert!(error.to_string().contains("unknown callback \"missing\""));
+
+    let mut scheduler = Scheduler::new().with_registry(registry());
+    let job = Job::new_registered(
+        "test".to_string(),
+        "missing",
+        Value::Null,
+        triggerSet![Interval::new(Duration::from_secs(1))],
+    );
+    assert_eq!(
+        scheduler.add_job(job),
+        Err(SchedulerError::UnknownCallback("missing".to_string()))
+    );
+}
+
+#[tokio::test]
+async fn test_run_timeout() {
+    let timeout = Duration::from_millis(50);
+    let run = |job: Job| async move {
+        job.with_timeout(timeout)
+            .execute(
+                &SystemClock,
+                DateTime::<Utc>::default(),
+                None,
+                CancellationToken::new(),
+                &CancellationToken::new(),
+            )
+            .await
+            .outcome
+    };
+
+    let stuck_async = Job::new_async(
+        "async".to_string(),
+        |_| async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(Value::Null)
+        },
+        Value::Null,
+        triggerSet![Interval::new(Duration::from_secs(1))],
+    );
+    assert_eq!(run(stuck_async).await, Err(JobError::TimedOut(timeout)));
+
+    fn stuck_blocking(_context: &Value) -> crate::job::Result<Value> {
+        std::thread::sleep(Duration::from_millis(200));
+        Ok(Value::Null)
+    }
+    let stuck_blocking = Job::new_fallible(
+        "blocking".to_string(),
+        stuck_blocking,
+        Value::Null,
+        triggerSet![Interval::new(Duration::from_secs(1))],
+    );
+    assert_eq!(run(stuck_blocking).await, Err(JobError::TimedOut(timeout)));
+}
+
+#[tokio::test(start_paused = true)]
+async fn limited_job_runs_out() {
+    let clock = Arc::new(TokioClock::new(dt_parse(DEFAULT_UTC)));
+    let runs = Arc::new(AtomicUsize::new(0));
+    let every_second = Interval::new(Duration::from_secs(1)).with_anchor(dt_parse(DEFAULT_UTC));
+    let job = Job::new_async(
+        "limited".to_string(),
+        {
+            let runs = runs.clone();
+            move |_context: Value| {
+                let runs = runs.clone();
+                async move {
+                    runs.fetch_add(1, Ordering::SeqCst);
+                    Ok(Value::Null)
+                }
+            }
+        },
+        Value::Null,
+        triggerSet![Limit::new(every_second, 3, dt_parse(DEFAULT_UTC))],
+    );
+
+    let mut join_set = JoinSet::new();
+    Job::run_with_clock(job, clock, &mut join_set);
+
+    let result = join_set.join_next().await.unwrap().unwrap();
+    assert_eq!(result, Err(JobError::NoMoreRuns));
+    assert_eq!(runs.load(Ordering::SeqCst), 3);
+}
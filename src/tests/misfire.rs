@@ -0,0 +1,163 @@
+use crate::tests::{dt_parse, DEFAULT_UTC};
+
+use crate::clock::{ManualClock, TokioClock};
+use crate::job::{Job, MisfirePolicy};
+use crate::scheduler::Scheduler;
+use crate::store::{JobStore, MemoryStore};
+use crate::trigger::{Cron, Interval, Oneshot, TriggerSet};
+use crate::triggerSet;
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::UTC;
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+
+// half past, so no fire lines up with the start of the test
+const NOW: &str = "2023-01-01T00:30:00Z";
+
+fn hourly() -> TriggerSet {
+    triggerSet![Cron::new("0 * * * *", UTC).unwrap()]
+}
+
+// starts the job's schedule at `since` and collects the runs reported before it goes quiet
+async fn runs(policy: MisfirePolicy, triggers: TriggerSet, since: DateTime<Utc>) -> Vec<String> {
+    let clock = TokioClock::new(dt_parse(NOW));
+    let job = Job::new("test".to_string(), None, Value::Null, triggers).with_misfire_policy(policy);
+    let (results_tx, mut results_rx) = mpsc::unbounded_channel();
+    let mut tasks = JoinSet::new();
+    Job::start_task(
+        &mut tasks,
+        Arc::new(job),
+        Arc::new(clock),
+        Some(since),
+        Some(results_tx),
+        CancellationToken::new(),
+    );
+
+    let mut runs = Vec::new();
+    while let Ok(Some(record)) =
+        tokio::time::timeout(std::time::Duration::from_millis(200), results_rx.recv()).await
+    {
+        runs.push(record.scheduled.to_rfc3339());
+    }
+    tasks.abort_all();
+    runs
+}
+
+#[tokio::test(start_paused = true)]
+async fn skip_missed_runs() {
+    let since = dt_parse(NOW) - Duration::minutes(240);
+    assert!(runs(MisfirePolicy::Skip, hourly(), since).await.is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn run_latest_missed_run_once() {
+    let since = dt_parse(NOW) - Duration::minutes(240);
+    assert_eq!(
+        runs(MisfirePolicy::RunOnce, hourly(), since).await,
+        vec!["2023-01-01T00:00:00+00:00"]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn run_all_missed_runs() {
+    let since = dt_parse(NOW) - Duration::minutes(240);
+    assert_eq!(
+        runs(MisfirePolicy::RunAll, hourly(), since).await,
+        vec![
+            "2022-12-31T21:00:00+00:00",
+            "2022-12-31T22:00:00+00:00",
+            "2022-12-31T23:00:00+00:00",
+            "2023-01-01T00:00:00+00:00",
+        ]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn cap_replayed_missed_runs() {
+    let every_second = Interval::new(std::time::Duration::from_secs(1))
+        .with_anchor(dt_parse(DEFAULT_UTC) - Duration::days(1));
+    let since = dt_parse(NOW) - Duration::hours(1);
+    let runs = runs(MisfirePolicy::RunAll, triggerSet![every_second], since).await;
+
+    assert_eq!(runs.len(), 1000);
+    assert_eq!(runs[0], "2022-12-31T23:30:01+00:00");
+    assert_eq!(runs[999], "2022-12-31T23:46:40+00:00");
+}
+
+// starts the job on time and moves its clock five hours ahead while it waits for the next fire
+async fn runs_after_clock_jump(policy: MisfirePolicy) -> Vec<String> {
+    let clock = ManualClock::new(dt_parse(NOW));
+    let job = Job::new("test".to_string(), None, Value::Null, hourly()).with_misfire_policy(policy);
+    let (results_tx, mut results_rx) = mpsc::unbounded_channel();
+    let mut tasks = JoinSet::new();
+    Job::start_task(
+        &mut tasks,
+        Arc::new(job),
+        Arc::new(clock.clone()),
+        None,
+        Some(results_tx),
+        CancellationToken::new(),
+    );
+    tokio::task::yield_now().await;
+    clock.advance(std::time::Duration::from_secs(5 * 3600));
+
+    let mut runs = Vec::new();
+    while let Ok(Some(record)) =
+        tokio::time::timeout(std::time::Duration::from_millis(200), results_rx.recv()).await
+    {
+        runs.push(record.scheduled.to_rfc3339());
+    }
+    tasks.abort_all();
+    runs
+}
+
+#[tokio::test(start_paused = true)]
+async fn apply_policy_to_fires_missed_while_waiting() {
+    assert!(runs_after_clock_jump(MisfirePolicy::Skip).await.is_empty());
+    assert_eq!(
+        runs_after_clock_jump(MisfirePolicy::RunOnce).await,
+        vec!["2023-01-01T05:00:00+00:00"]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn run_missed_runs_within_grace_period() {
+    let grace = MisfirePolicy::Grace(std::time::Duration::from_secs(3600));
+    let missed_at = |hours| Oneshot::new(dt_parse(DEFAULT_UTC) - Duration::hours(hours));
+    let since = dt_parse(DEFAULT_UTC) - Duration::days(1);
+
+    assert_eq!(
+        runs(grace, triggerSet![missed_at(0)], since).await,
+        vec!["2023-01-01T00:00:00+00:00"]
+    );
+    assert!(runs(grace, triggerSet![missed_at(2)], since)
+        .await
+        .is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn run_oneshot_missed_during_restart() {
+    let clock = TokioClock::new(dt_parse(DEFAULT_UTC));
+    let due = dt_parse(DEFAULT_UTC) - Duration::hours(1);
+    let mut job = Job::new(
+        "billing".to_string(),
+        None,
+        Value::Null,
+        triggerSet![Oneshot::new(due)],
+    )
+    .with_misfire_policy(MisfirePolicy::RunOnce);
+    job.set_last_run(Some(due - Duration::days(1)));
+    let store = MemoryStore::new();
+    store.upsert(&job).await.unwrap();
+
+    let scheduler = Scheduler::new().with_clock(clock).with_store(store);
+    let mut results = scheduler.subscribe();
+    scheduler.run().await;
+
+    let record = results.try_recv().unwrap();
+    assert_eq!((record.job.as_str(), record.scheduled), ("billing", due));
+    assert!(results.try_recv().is_err());
+}
@@ -1,43 +1,44 @@
 #![cfg(test)]
 
-mod fake_time;
+mod calendar;
+mod clock;
+mod cron;
+mod dependency;
+mod event;
+mod history;
 mod job;
+mod misfire;
+mod overlap;
+mod retry;
 mod scheduler;
+mod store;
 mod trigger;
 
-use crate::job::Job;
-use crate::tests::fake_time::Config;
-use crate::trigger::interval::Interval;
-use crate::trigger::oneshot::Oneshot;
-use crate::trigger::weekly::Weekly;
-use crate::trigger::NowUtc;
-
+use crate::job::Result;
+use crate::trigger::Trigger;
 use chrono::{DateTime, Utc};
+use serde_json::Value;
 
-impl NowUtc for Oneshot {
-    fn now_utc() -> DateTime<Utc> {
-        Config::get_fake_now()
-    }
+pub fn dt_parse(dt_str: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(dt_str)
+        .unwrap()
+        .with_timezone(&Utc)
 }
 
-impl NowUtc for Interval {
-    fn now_utc() -> DateTime<Utc> {
-        Config::get_fake_now()
-    }
-}
+pub const DEFAULT_UTC: &str = "2023-01-01T00:00:00Z";
+pub const DST_SPRING_LOCAL: &str = "2023-03-24T01:00:00+01:00";
+pub const DST_AUTUMN_LOCAL: &str = "2023-10-27T01:00:00+01:00";
 
-impl NowUtc for Weekly {
-    fn now_utc() -> DateTime<Utc> {
-        Config::get_fake_now()
-    }
+/// The first `n` fires of `trigger` after `from`, in RFC 3339.
+pub fn fires(trigger: &dyn Trigger, from: &str, n: usize) -> Vec<String> {
+    trigger
+        .occurrences(dt_parse(from))
+        .take(n)
+        .map(|dt| dt.to_rfc3339())
+        .collect()
 }
 
-impl NowUtc for Job {
-    fn now_utc() -> DateTime<Utc> {
-        Config::get_fake_now()
-    }
+/// A callback returning its context.
+pub fn echo(context: &Value) -> Result<Value> {
+    Ok(context.clone())
 }
-
-pub const DEFAULT_UTC: &str = "2023-01-01T00:00:00Z";
-pub const DST_SPRING_LOCAL: &str = "2023-03-24T01:00:00+01:00";
-pub const DST_AUTUMN_LOCAL: &str = "2023-10-27T01:00:00+01:00";
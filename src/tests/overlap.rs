@@ -0,0 +1,84 @@
+use crate::tests::{dt_parse, DEFAULT_UTC};
+
+use crate::clock::TokioClock;
+use crate::error::JobError;
+use crate::job::{Job, OverlapPolicy};
+use crate::trigger::Interval;
+use crate::triggerSet;
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+
+// fires every 200ms for 1.1s with a callback that takes 500ms, returning which fires ran and
+// whether they completed
+async fn overlapping_runs(policy: OverlapPolicy) -> Vec<(i64, bool)> {
+    let clock = TokioClock::new(dt_parse(DEFAULT_UTC));
+    let job = Job::new_async(
+        "slow".to_string(),
+        |_| async {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            Ok(Value::Null)
+        },
+        Value::Null,
+        triggerSet![Interval::new(Duration::from_millis(200))],
+    )
+    .with_overlap_policy(policy);
+    let (results_tx, mut results_rx) = mpsc::unbounded_channel();
+    let shutdown = CancellationToken::new();
+    let mut tasks = JoinSet::new();
+    Job::start_task(
+        &mut tasks,
+        Arc::new(job),
+        Arc::new(clock),
+        Some(dt_parse(DEFAULT_UTC)),
+        Some(results_tx),
+        shutdown.clone(),
+    );
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    shutdown.cancel();
+    tasks.join_next().await.unwrap().unwrap().unwrap();
+
+    let mut runs = Vec::new();
+    while let Ok(record) = results_rx.try_recv() {
+        let fire = (record.scheduled - dt_parse(DEFAULT_UTC)).num_milliseconds() / 200;
+        runs.push((fire, record.outcome != Err(JobError::Cancelled)));
+    }
+    runs.sort();
+    runs
+}
+
+#[tokio::test(start_paused = true)]
+async fn skip_overlapping_fires() {
+    assert_eq!(
+        overlapping_runs(OverlapPolicy::Skip).await,
+        vec![(1, true), (4, true)]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn queue_one_overlapping_fire() {
+    assert_eq!(
+        overlapping_runs(OverlapPolicy::Queue).await,
+        vec![(1, true), (2, true)]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn allow_concurrent_runs() {
+    assert_eq!(
+        overlapping_runs(OverlapPolicy::Concurrent(2)).await,
+        vec![(1, true), (2, true), (4, true), (5, true)]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn replace_running_fire() {
+    assert_eq!(
+        overlapping_runs(OverlapPolicy::Replace).await,
+        vec![(1, false), (2, false), (3, false), (4, false), (5, true)]
+    );
+}
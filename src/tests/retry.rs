@@ -0,0 +1,85 @@
+use crate::tests::{dt_parse, DEFAULT_UTC};
+
+use crate::clock::TokioClock;
+use crate::error::JobError;
+use crate::job::{Job, RetryPolicy};
+use crate::trigger::Oneshot;
+use crate::triggerSet;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+
+#[test]
+fn backoff_delays() {
+    let retry_policy = RetryPolicy::new(4, Duration::from_millis(100))
+        .with_multiplier(3.0)
+        .with_max_delay(Duration::from_millis(500));
+    let delays: Vec<_> = (1..=4).map(|attempt| retry_policy.delay(attempt)).collect();
+    assert_eq!(
+        delays,
+        vec![
+            Some(Duration::from_millis(100)),
+            Some(Duration::from_millis(300)),
+            Some(Duration::from_millis(500)),
+            None,
+        ]
+    );
+
+    let jittered = retry_policy.with_jitter(Duration::from_millis(50));
+    for _ in 0..20 {
+        let delay = jittered.delay(1).unwrap();
+        assert!((Duration::from_millis(100)..=Duration::from_millis(150)).contains(&delay));
+    }
+}
+
+#[tokio::test]
+async fn retry_failed_runs() {
+    let clock = TokioClock::new(dt_parse(DEFAULT_UTC));
+    let scheduled = dt_parse(DEFAULT_UTC) + Duration::from_millis(50);
+    let calls = Arc::new(AtomicUsize::new(0));
+    let job = Job::new_async(
+        "flaky".to_string(),
+        move |_| {
+            let calls = calls.clone();
+            async move {
+                match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => Err(JobError::failed("connection reset")),
+                    _ => Ok(json!("done")),
+                }
+            }
+        },
+        Value::Null,
+        triggerSet![Oneshot::new(scheduled)],
+    )
+    .with_retry_policy(RetryPolicy::new(5, Duration::from_millis(20)));
+
+    let (results_tx, mut results_rx) = mpsc::unbounded_channel();
+    let mut tasks = JoinSet::new();
+    Job::start_task(
+        &mut tasks,
+        Arc::new(job),
+        Arc::new(clock),
+        None,
+        Some(results_tx),
+        CancellationToken::new(),
+    );
+    tasks.join_next().await;
+
+    let mut attempts = Vec::new();
+    while let Ok(record) = results_rx.try_recv() {
+        assert_eq!(record.scheduled, scheduled);
+        attempts.push((record.attempt, record.outcome));
+    }
+    assert_eq!(
+        attempts,
+        vec![
+            (1, Err(JobError::failed("connection reset"))),
+            (2, Err(JobError::failed("connection reset"))),
+            (3, Ok(json!("done"))),
+        ]
+    );
+}
@@ -1,20 +1,23 @@
-use crate::tests::fake_time::{dt_parse, set_start_time};
-use crate::tests::DEFAULT_UTC;
+use crate::tests::{dt_parse, DEFAULT_UTC};
 
-use crate::job::Job;
-use crate::scheduler::Scheduler;
-use crate::trigger::Oneshot;
+use crate::clock::TokioClock;
+use crate::error::{JobError, SchedulerError};
+use crate::job::{Job, Result};
+use crate::scheduler::{JobInfo, Scheduler};
+use crate::trigger::{Cron, Oneshot};
 use crate::triggerSet;
 
-use serde_json::Value;
+use chrono_tz::UTC;
+use serde_json::{json, Value};
+use std::collections::HashMap;
 
-fn callback(context: &Value) {
+fn callback(_context: &Value) {
     println!("test scheduler callback");
 }
 
 #[tokio::test]
 async fn it_works_utc() {
-    set_start_time(DEFAULT_UTC);
+    let clock = TokioClock::new(dt_parse(DEFAULT_UTC));
     let test_time = dt_parse(DEFAULT_UTC);
     let oneshot = Oneshot::new(test_time + std::time::Duration::from_secs(1));
     let job = Job::new(
@@ -23,7 +26,374 @@ async fn it_works_utc() {
         Value::Null,
         triggerSet![oneshot],
     );
-    let mut scheduler = Scheduler::new();
-    scheduler.add_job(job);
+    let mut scheduler = Scheduler::new().with_clock(clock);
+    scheduler.add_job(job).unwrap();
     scheduler.run().await;
 }
+
+#[tokio::test]
+async fn records_run_outcomes() {
+    let clock = TokioClock::new(dt_parse(DEFAULT_UTC));
+    let test_time = dt_parse(DEFAULT_UTC);
+    let oneshot = || Oneshot::new(test_time + std::time::Duration::from_millis(100));
+
+    fn succeeding(context: &Value) -> Result<Value> {
+        Ok(json!({ "echo": context }))
+    }
+
+    fn failing(_context: &Value) -> Result<Value> {
+        Err(JobError::failed("database unavailable"))
+    }
+
+    let mut scheduler = Scheduler::new().with_clock(clock);
+    scheduler
+        .add_job(Job::new_fallible(
+            "succeeding".to_string(),
+            succeeding,
+            json!(42),
+            triggerSet![oneshot()],
+        ))
+        .unwrap();
+    scheduler
+        .add_job(Job::new_fallible(
+            "failing".to_string(),
+            failing,
+            Value::Null,
+            triggerSet![oneshot()],
+        ))
+        .unwrap();
+    scheduler
+        .add_job(Job::new_async(
+            "panicking".to_string(),
+            |_| async { panic!("boom") },
+            Value::Null,
+            triggerSet![oneshot()],
+        ))
+        .unwrap();
+    let mut results = scheduler.subscribe();
+    scheduler.run().await;
+
+    let mut outcomes = HashMap::new();
+    while let Ok(record) = results.try_recv() {
+        assert_eq!(
+            record.scheduled,
+            test_time + std::time::Duration::from_millis(100)
+        );
+        outcomes.insert(record.job, record.outcome);
+    }
+
+    assert_eq!(outcomes["succeeding"], Ok(json!({ "echo": 42 })));
+    assert_eq!(
+        outcomes["failing"],
+        Err(JobError::Failed("database unavailable".to_string()))
+    );
+    assert_eq!(
+        outcomes["panicking"],
+        Err(JobError::Panicked("boom".to_string()))
+    );
+}
+
+#[tokio::test]
+async fn control_running_scheduler_through_handle() {
+    let clock = TokioClock::new(dt_parse(DEFAULT_UTC));
+    let test_time = dt_parse(DEFAULT_UTC);
+    let next_run = test_time + std::time::Duration::from_secs(3600);
+    let job = |name: &str| {
+        Job::new(
+            name.to_string(),
+            Some(callback),
+            Value::Null,
+            triggerSet![Oneshot::new(next_run)],
+        )
+    };
+
+    let mut scheduler = Scheduler::new().with_clock(clock);
+    scheduler.add_job(job("first")).unwrap();
+    assert_eq!(
+        scheduler.add_job(job("first")),
+        Err(SchedulerError::DuplicateJob("first".to_string()))
+    );
+    let handle = scheduler.handle();
+    let mut results = scheduler.subscribe();
+    let scheduler = tokio::spawn(scheduler.run());
+
+    handle.add_job(job("second")).await.unwrap();
+    assert_eq!(
+        handle.add_job(job("second")).await,
+        Err(SchedulerError::DuplicateJob("second".to_string()))
+    );
+    assert_eq!(
+        handle.jobs().await.unwrap(),
+        vec![
+            JobInfo {
+                name: "first".to_string(),
+                paused: false,
+                next_run: Some(next_run),
+            },
+            JobInfo {
+                name: "second".to_string(),
+                paused: false,
+                next_run: Some(next_run),
+            },
+        ]
+    );
+
+    handle.pause_job("first").await.unwrap();
+    let jobs = handle.jobs().await.unwrap();
+    assert!(jobs[0].paused);
+    assert_eq!(jobs[0].next_run, None);
+    handle.resume_job("first").await.unwrap();
+    assert!(!handle.jobs().await.unwrap()[0].paused);
+
+    handle.trigger_job("second").await.unwrap();
+    let record = results.recv().await.unwrap();
+    assert_eq!(record.job, "second");
+    assert_eq!(record.outcome, Ok(Value::Null));
+
+    assert_eq!(
+        handle.trigger_job("third").await,
+        Err(SchedulerError::UnknownJob("third".to_string()))
+    );
+
+    handle.remove_job("first").await.unwrap();
+    handle.remove_job("second").await.unwrap();
+    assert_eq!(handle.jobs().await.unwrap(), vec![]);
+
+    drop(handle);
+    scheduler.await.unwrap();
+}
+
+#[tokio::test]
+async fn shutdown_drains_in_flight_runs() {
+    let clock = TokioClock::new(dt_parse(DEFAULT_UTC));
+    let idle_run = dt_parse(DEFAULT_UTC) + std::time::Duration::from_secs(3600);
+    let sleeping_job = |name: &str, duration: std::time::Duration| {
+        Job::new_async(
+            name.to_string(),
+            move |_| async move {
+                tokio::time::sleep(duration).await;
+                Ok(Value::Null)
+            },
+            Value::Null,
+            triggerSet![Oneshot::new(idle_run)],
+        )
+    };
+
+    let mut scheduler = Scheduler::new().with_clock(clock);
+    scheduler
+        .add_job(sleeping_job("quick", std::time::Duration::from_millis(100)))
+        .unwrap();
+    scheduler
+        .add_job(sleeping_job("stuck", std::time::Duration::from_secs(60)))
+        .unwrap();
+    scheduler
+        .add_job(sleeping_job("idle", std::time::Duration::ZERO))
+        .unwrap();
+    let handle = scheduler.handle();
+    let mut results = scheduler.subscribe();
+    let scheduler = tokio::spawn(scheduler.run());
+
+    handle.trigger_job("quick").await.unwrap();
+    handle.trigger_job("stuck").await.unwrap();
+    let summary = handle
+        .shutdown(std::time::Duration::from_millis(500))
+        .await
+        .unwrap();
+
+    assert_eq!(summary.interrupted, vec!["stuck".to_string()]);
+    let record = results.recv().await.unwrap();
+    assert_eq!(record.job, "quick");
+    assert!(record.is_success());
+
+    scheduler.await.unwrap();
+    assert_eq!(handle.jobs().await, Err(SchedulerError::Stopped));
+}
+
+#[tokio::test]
+async fn shutdown_stops_cancellable_runs() {
+    let clock = TokioClock::new(dt_parse(DEFAULT_UTC));
+    let mut scheduler = Scheduler::new().with_clock(clock);
+    scheduler
+        .add_job(Job::new_cancellable(
+            "cooperative".to_string(),
+            |_, stop| async move {
+                stop.cancelled().await;
+                Ok(json!("stopped"))
+            },
+            Value::Null,
+            triggerSet![Oneshot::new(
+                dt_parse(DEFAULT_UTC) + std::time::Duration::from_millis(50)
+            )],
+        ))
+        .unwrap();
+    let handle = scheduler.handle();
+    let mut results = scheduler.subscribe();
+    let scheduler = tokio::spawn(scheduler.run());
+
+    tokio::time::sleep(std::time::Duration::from_millis(150)).await;
+    let summary = handle
+        .shutdown(std::time::Duration::from_secs(5))
+        .await
+        .unwrap();
+
+    assert!(summary.interrupted.is_empty());
+    assert_eq!(results.recv().await.unwrap().outcome, Ok(json!("stopped")));
+    scheduler.await.unwrap();
+}
+
+#[tokio::test(start_paused = true)]
+async fn workers_bound_concurrent_runs() {
+    let clock = TokioClock::new(dt_parse(DEFAULT_UTC));
+    let fire = dt_parse(DEFAULT_UTC) + std::time::Duration::from_millis(100);
+    let mut scheduler = Scheduler::new().with_clock(clock).with_workers(2);
+    for name in ["a", "b", "c", "d"] {
+        scheduler
+            .add_job(Job::new_async(
+                name.to_string(),
+                |_| async {
+                    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                    Ok(Value::Null)
+                },
+                Value::Null,
+                triggerSet![Oneshot::new(fire)],
+            ))
+            .unwrap();
+    }
+    let mut results = scheduler.subscribe();
+    scheduler.run().await;
+
+    let mut started = Vec::new();
+    while let Ok(record) = results.try_recv() {
+        assert_eq!(record.scheduled, fire);
+        started.push((record.job, (record.started - fire).num_seconds()));
+    }
+    started.sort();
+    assert_eq!(
+        started,
+        vec![
+            ("a".to_string(), 0),
+            ("b".to_string(), 0),
+            ("c".to_string(), 1),
+            ("d".to_string(), 1),
+        ]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn drop_waiting_runs_of_removed_and_paused_jobs() {
+    let clock = TokioClock::new(dt_parse(DEFAULT_UTC));
+    let fire = dt_parse(DEFAULT_UTC) + std::time::Duration::from_millis(100);
+    let mut scheduler = Scheduler::new().with_clock(clock).with_workers(1);
+    for (name, at) in [
+        ("slow", fire),
+        ("removed", fire + std::time::Duration::from_millis(10)),
+        ("paused", fire + std::time::Duration::from_millis(10)),
+    ] {
+        scheduler
+            .add_job(Job::new_async(
+                name.to_string(),
+                |_| async {
+                    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                    Ok(Value::Null)
+                },
+                Value::Null,
+                triggerSet![Oneshot::new(at)],
+            ))
+            .unwrap();
+    }
+    let handle = scheduler.handle();
+    let mut results = scheduler.subscribe();
+    let scheduler = tokio::spawn(scheduler.run());
+
+    // both wait for the worker "slow" holds
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    handle.remove_job("removed").await.unwrap();
+    handle.pause_job("paused").await.unwrap();
+    drop(handle);
+    scheduler.await.unwrap();
+
+    assert_eq!(results.try_recv().unwrap().job, "slow");
+    assert!(results.try_recv().is_err());
+}
+
+#[tokio::test(start_paused = true)]
+async fn concurrency_groups_limit_their_jobs() {
+    let clock = TokioClock::new(dt_parse(DEFAULT_UTC));
+    let fire = dt_parse(DEFAULT_UTC) + std::time::Duration::from_millis(100);
+    let slow_job = |name: &str| {
+        Job::new_async(
+            name.to_string(),
+            |_| async {
+                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                Ok(Value::Null)
+            },
+            Value::Null,
+            triggerSet![Oneshot::new(fire)],
+        )
+    };
+    let mut scheduler = Scheduler::new()
+        .with_clock(clock)
+        .with_concurrency_group("db-heavy", 2);
+    for name in ["export", "reindex", "vacuum"] {
+        scheduler
+            .add_job(slow_job(name).with_concurrency_group("db-heavy"))
+            .unwrap();
+    }
+    scheduler.add_job(slow_job("ping")).unwrap();
+    let mut results = scheduler.subscribe();
+    scheduler.run().await;
+
+    let mut started = Vec::new();
+    while let Ok(record) = results.try_recv() {
+        started.push((record.job, (record.started - fire).num_seconds()));
+    }
+    started.sort();
+    assert_eq!(
+        started,
+        vec![
+            ("export".to_string(), 0),
+            ("ping".to_string(), 0),
+            ("reindex".to_string(), 0),
+            ("vacuum".to_string(), 1),
+        ]
+    );
+}
+
+#[test]
+fn preview_upcoming_fires() {
+    let daily = |name: &str, expression: &str| {
+        Job::new(
+            name.to_string(),
+            Some(callback),
+            Value::Null,
+            triggerSet![Cron::new(expression, UTC).unwrap()],
+        )
+    };
+    let mut scheduler = Scheduler::new();
+    scheduler.add_job(daily("nightly", "0 2 * * *")).unwrap();
+    scheduler
+        .add_job(daily("weekend-report", "0 9 * * SAT,SUN"))
+        .unwrap();
+    scheduler
+        .add_job(daily("weekday-sync", "0 8 * * MON-FRI"))
+        .unwrap();
+
+    let fires: Vec<(&str, String)> = scheduler
+        .preview(
+            dt_parse("2023-01-06T18:00:00Z"),
+            dt_parse("2023-01-09T08:00:00Z"),
+        )
+        .into_iter()
+        .map(|fire| (fire.job, fire.at.to_rfc3339()))
+        .collect();
+    assert_eq!(
+        fires,
+        vec![
+            ("nightly", "2023-01-07T02:00:00+00:00".to_string()),
+            ("weekend-report", "2023-01-07T09:00:00+00:00".to_string()),
+            ("nightly", "2023-01-08T02:00:00+00:00".to_string()),
+            ("weekend-report", "2023-01-08T09:00:00+00:00".to_string()),
+            ("nightly", "2023-01-09T02:00:00+00:00".to_string()),
+        ]
+    );
+}
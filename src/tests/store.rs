@@ -0,0 +1,365 @@
+use crate::tests::{dt_parse, DEFAULT_UTC};
+
+use crate::clock::TokioClock;
+use crate::job::{
+    Callback, CallbackRegistry, Job, MisfirePolicy, OverlapPolicy, RunHistory, RunRecord,
+};
+use crate::scheduler::Scheduler;
+use crate::store::{JobStore, JsonFileStore, MemoryStore};
+use crate::trigger::{Cron, Interval, Oneshot};
+use crate::triggerSet;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use chrono_tz::UTC;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn job(name: &str) -> Job {
+    Job::new(
+        name.to_string(),
+        None,
+        json!({ "name": name }),
+        triggerSet![Interval::new(Duration::from_secs(60))],
+    )
+}
+
+async fn exercise_store(store: &impl JobStore) {
+    assert_eq!(store.load_all().await.unwrap(), vec![]);
+
+    store.upsert(&job("first")).await.unwrap();
+    store.upsert(&job("second")).await.unwrap();
+    store.upsert(&job("second")).await.unwrap();
+    let last_run = dt_parse(DEFAULT_UTC);
+    store.record_last_run("first", last_run).await.unwrap();
+    store.record_last_run("unknown", last_run).await.unwrap();
+    // an earlier run finishing late doesn't move it back
+    store
+        .record_last_run("first", last_run - chrono::Duration::hours(1))
+        .await
+        .unwrap();
+
+    let mut expected_first = job("first");
+    expected_first.set_last_run(Some(last_run));
+    assert_eq!(
+        store.load_all().await.unwrap(),
+        vec![expected_first, job("second")]
+    );
+
+    let mut history = RunHistory::new(10);
+    history.push(RunRecord {
+        job: "first".to_string(),
+        scheduled: last_run,
+        started: last_run,
+        duration: Duration::from_millis(250),
+        outcome: Ok(json!("done")),
+        attempt: 1,
+        manual: false,
+        trigger: Some(json!({ "type": "Interval", "interval": { "secs": 60, "nanos": 0 } })),
+    });
+    store.record_history("first", &history).await.unwrap();
+    store.record_history("unknown", &history).await.unwrap();
+    store.upsert(&job("first")).await.unwrap();
+    let histories = store.load_histories().await.unwrap();
+    assert_eq!(histories.len(), 1);
+    assert_eq!(histories["first"], history);
+
+    let later = last_run + Duration::from_secs(60);
+    history.push(RunRecord {
+        scheduled: later,
+        started: later,
+        ..history.records().last().unwrap().clone()
+    });
+    store
+        .record_runs(
+            &BTreeMap::from([("first".to_string(), later), ("unknown".to_string(), later)]),
+            &BTreeMap::from([("first".to_string(), history.clone())]),
+        )
+        .await
+        .unwrap();
+    assert_eq!(store.load_all().await.unwrap()[0].last_run(), Some(later));
+    assert_eq!(store.load_histories().await.unwrap()["first"], history);
+
+    store.record_paused("second", true).await.unwrap();
+    store.record_paused("unknown", true).await.unwrap();
+    assert!(store.load_all().await.unwrap()[1].is_paused());
+    store.record_paused("second", false).await.unwrap();
+    assert_eq!(store.load_all().await.unwrap()[1], job("second"));
+
+    store.delete("second").await.unwrap();
+    assert_eq!(store.load_all().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn memory_store() {
+    exercise_store(&MemoryStore::new()).await;
+}
+
+#[tokio::test]
+async fn json_file_store() {
+    let path = std::env::temp_dir().join(format!("scheduler-store-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    exercise_store(&JsonFileStore::new(&path)).await;
+    let reopened = JsonFileStore::new(&path);
+    assert_eq!(reopened.load_all().await.unwrap()[0].name, "first");
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn scheduler_restores_and_persists_jobs() {
+    let clock = TokioClock::new(dt_parse(DEFAULT_UTC));
+    let run_time = dt_parse(DEFAULT_UTC) + Duration::from_millis(100);
+    let path = std::env::temp_dir().join(format!("scheduler-restore-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let store = JsonFileStore::new(&path);
+    store
+        .upsert(&Job::new(
+            "stored".to_string(),
+            None,
+            Value::Null,
+            triggerSet![Oneshot::new(run_time)],
+        ))
+        .await
+        .unwrap();
+
+    let mut scheduler = Scheduler::new()
+        .with_clock(clock)
+        .with_store(JsonFileStore::new(&path));
+    scheduler.add_job(job("added")).unwrap();
+    let handle = scheduler.handle();
+    let mut results = scheduler.subscribe();
+    let scheduler = tokio::spawn(scheduler.run());
+
+    let record = results.recv().await.unwrap();
+    assert_eq!(record.job, "stored");
+    let names: Vec<String> = handle
+        .jobs()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|job| job.name)
+        .collect();
+    assert_eq!(names, vec!["added", "stored"]);
+
+    handle.remove_job("added").await.unwrap();
+    handle.pause_job("stored").await.unwrap();
+    drop(handle);
+    scheduler.await.unwrap();
+
+    let stored = store.load_all().await.unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].name, "stored");
+    assert_eq!(stored[0].last_run(), Some(run_time));
+    assert!(stored[0].is_paused());
+
+    // still paused after a restart, until resumed
+    let scheduler = Scheduler::new()
+        .with_clock(TokioClock::new(dt_parse(DEFAULT_UTC)))
+        .with_store(JsonFileStore::new(&path));
+    let handle = scheduler.handle();
+    let scheduler = tokio::spawn(scheduler.run());
+    let jobs = handle.jobs().await.unwrap();
+    assert_eq!((jobs[0].name.as_str(), jobs[0].paused), ("stored", true));
+    handle.resume_job("stored").await.unwrap();
+    drop(handle);
+    scheduler.await.unwrap();
+    assert!(!store.load_all().await.unwrap()[0].is_paused());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+// takes ten seconds for every write a run leaves behind
+#[derive(Default)]
+struct SlowStore {
+    inner: Arc<MemoryStore>,
+    batches: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl JobStore for SlowStore {
+    async fn load_all(&self) -> crate::store::Result<Vec<Job>> {
+        self.inner.load_all().await
+    }
+
+    async fn upsert(&self, job: &Job) -> crate::store::Result<()> {
+        self.inner.upsert(job).await
+    }
+
+    async fn delete(&self, name: &str) -> crate::store::Result<()> {
+        self.inner.delete(name).await
+    }
+
+    async fn record_last_run(&self, name: &str, at: DateTime<Utc>) -> crate::store::Result<()> {
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        self.inner.record_last_run(name, at).await
+    }
+
+    async fn record_paused(&self, name: &str, paused: bool) -> crate::store::Result<()> {
+        self.inner.record_paused(name, paused).await
+    }
+
+    async fn record_history(&self, name: &str, history: &RunHistory) -> crate::store::Result<()> {
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        self.inner.record_history(name, history).await
+    }
+
+    async fn record_runs(
+        &self,
+        last_runs: &BTreeMap<String, DateTime<Utc>>,
+        histories: &BTreeMap<String, RunHistory>,
+    ) -> crate::store::Result<()> {
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        self.batches.fetch_add(1, Ordering::SeqCst);
+        self.inner.record_runs(last_runs, histories).await
+    }
+
+    async fn load_histories(&self) -> crate::store::Result<BTreeMap<String, RunHistory>> {
+        self.inner.load_histories().await
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn slow_store_doesnt_hold_up_runs() {
+    let clock = TokioClock::new(dt_parse(DEFAULT_UTC));
+    let store = SlowStore::default();
+    let (stored, batches) = (store.inner.clone(), store.batches.clone());
+    let every_second = Interval::new(Duration::from_secs(1)).with_anchor(dt_parse(DEFAULT_UTC));
+    let mut scheduler = Scheduler::new().with_clock(clock).with_store(store);
+    scheduler
+        .add_job(Job::new(
+            "busy".to_string(),
+            None,
+            Value::Null,
+            triggerSet![every_second],
+        ))
+        .unwrap();
+    let handle = scheduler.handle();
+    let mut results = scheduler.subscribe();
+    let started = tokio::time::Instant::now();
+    let scheduler = tokio::spawn(scheduler.run());
+
+    let mut last_run = None;
+    for _ in 0..20 {
+        last_run = Some(results.recv().await.unwrap().scheduled);
+    }
+    assert!(started.elapsed() < Duration::from_secs(21));
+    handle.shutdown(Duration::from_secs(1)).await.unwrap();
+    scheduler.await.unwrap();
+    while let Ok(record) = results.try_recv() {
+        last_run = Some(record.scheduled);
+    }
+
+    // everything is written by the time the scheduler stops, in fewer writes than runs
+    assert_eq!(stored.load_all().await.unwrap()[0].last_run(), last_run);
+    let histories = stored.load_histories().await.unwrap();
+    assert_eq!(
+        histories["busy"].records().last().unwrap().scheduled,
+        last_run.unwrap()
+    );
+    assert!(batches.load(Ordering::SeqCst) < 10);
+}
+
+#[tokio::test(start_paused = true)]
+async fn keep_latest_fire_when_runs_finish_out_of_order() {
+    let clock = TokioClock::new(dt_parse(DEFAULT_UTC) + Duration::from_secs(30 * 60));
+    let path = std::env::temp_dir().join(format!("scheduler-order-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let started = Arc::new(AtomicUsize::new(0));
+
+    // the first run takes 90 minutes, so the second one finishes before it
+    let runs = started.clone();
+    let billing = Job::new_async(
+        "billing".to_string(),
+        move |_| {
+            let minutes = match runs.fetch_add(1, Ordering::SeqCst) {
+                0 => 90,
+                _ => 1,
+            };
+            async move {
+                tokio::time::sleep(Duration::from_secs(minutes * 60)).await;
+                Ok(Value::Null)
+            }
+        },
+        Value::Null,
+        triggerSet![Cron::new("0 * * * *", UTC).unwrap()],
+    )
+    .with_overlap_policy(OverlapPolicy::Concurrent(2))
+    .with_misfire_policy(MisfirePolicy::RunOnce);
+    let mut scheduler = Scheduler::new()
+        .with_clock(clock)
+        .with_store(JsonFileStore::new(&path));
+    scheduler.add_job(billing).unwrap();
+    let handle = scheduler.handle();
+    let mut results = scheduler.subscribe();
+    let scheduler = tokio::spawn(scheduler.run());
+
+    let finished: Vec<_> = [results.recv().await, results.recv().await]
+        .into_iter()
+        .map(|record| record.unwrap().scheduled)
+        .collect();
+    assert_eq!(
+        finished,
+        [
+            dt_parse("2023-01-01T02:00:00Z"),
+            dt_parse("2023-01-01T01:00:00Z")
+        ]
+    );
+    handle.shutdown(Duration::from_secs(1)).await.unwrap();
+    scheduler.await.unwrap();
+
+    let stored = JsonFileStore::new(&path).load_all().await.unwrap();
+    assert_eq!(stored[0].last_run(), Some(dt_parse("2023-01-01T02:00:00Z")));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn scheduler_resolves_restored_callbacks() {
+    let clock = TokioClock::new(dt_parse(DEFAULT_UTC));
+    let run_time = dt_parse(DEFAULT_UTC) + Duration::from_millis(100);
+    let store = MemoryStore::new();
+    for (name, callback) in [("known", "double"), ("unknown", "missing")] {
+        store
+            .upsert(&Job::new_registered(
+                name.to_string(),
+                callback,
+                json!(21),
+                triggerSet![Oneshot::new(run_time)],
+            ))
+            .await
+            .unwrap();
+    }
+
+    fn double(context: &Value) -> crate::job::Result<Value> {
+        Ok(json!(context.as_i64().unwrap() * 2))
+    }
+    let mut registry = CallbackRegistry::new();
+    registry.register("double", Callback::Fallible(double));
+
+    let scheduler = Scheduler::new()
+        .with_clock(clock)
+        .with_store(store)
+        .with_registry(registry);
+    let handle = scheduler.handle();
+    let mut results = scheduler.subscribe();
+    let scheduler = tokio::spawn(scheduler.run());
+
+    let names: Vec<String> = handle
+        .jobs()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|job| job.name)
+        .collect();
+    assert_eq!(names, vec!["known"]);
+    let record = results.recv().await.unwrap();
+    assert_eq!(record.outcome, Ok(json!(42)));
+
+    drop(handle);
+    scheduler.await.unwrap();
+}
@@ -1,19 +1,23 @@
-use crate::tests::fake_time::{dt_parse, set_start_time};
-use crate::tests::{DEFAULT_UTC, DST_AUTUMN_LOCAL, DST_SPRING_LOCAL};
+use crate::tests::{dt_parse, fires, DEFAULT_UTC, DST_AUTUMN_LOCAL, DST_SPRING_LOCAL};
 
-use crate::trigger::{Interval, Oneshot, Trigger, Weekly};
-use chrono::{DateTime, Duration, Local, Utc};
-use chrono_tz::{Europe::Berlin, UTC};
+use crate::clock::FixedClock;
+use crate::trigger::{
+    Between, DstFold, DstGap, Interval, Limit, MissingDay, MonthDay, MonthDayError, Monthly,
+    Oneshot, Trigger, Until, Weekly, Yearly,
+};
+use crate::triggerSet;
+use chrono::{DateTime, Duration, Local, Utc, Weekday};
+use chrono_tz::{America::New_York, Australia::Lord_Howe, Europe::Berlin, UTC};
 
 #[test]
 fn it_works_utc() {
-    set_start_time(DEFAULT_UTC);
+    let clock = FixedClock::new(dt_parse(DEFAULT_UTC));
     let weekly = Weekly::new(
         [false, true, true, true, true, true, true],
         Duration::hours(12).to_std().unwrap(),
         UTC,
     );
-    let ttnr: Vec<DateTime<Utc>> = weekly.next_runs(9).unwrap();
+    let ttnr: Vec<DateTime<Utc>> = weekly.next_runs(&clock, 9).unwrap();
 
     let expected_ttnr_utc: Vec<DateTime<Utc>> = [
         "2023-01-01T12:00:00Z",
@@ -34,13 +38,13 @@ fn it_works_utc() {
 
 #[test]
 fn it_works_local() {
-    set_start_time(DEFAULT_UTC);
+    let clock = FixedClock::new(dt_parse(DEFAULT_UTC));
     let weekly = Weekly::new(
         [false, true, true, true, true, true, true],
         Duration::hours(12).to_std().unwrap(),
         Berlin,
     );
-    let ttnr: Vec<DateTime<Utc>> = weekly.next_runs(9).unwrap();
+    let ttnr: Vec<DateTime<Utc>> = weekly.next_runs(&clock, 9).unwrap();
 
     let expected_ttnr_local: Vec<DateTime<Utc>> = [
         "2023-01-01T12:00:00+01:00",
@@ -65,13 +69,13 @@ fn it_works_local() {
 
 #[test]
 fn it_works_local_dst_change_spring() {
-    set_start_time(DST_SPRING_LOCAL);
+    let clock = FixedClock::new(dt_parse(DST_SPRING_LOCAL));
     let weekly = Weekly::new(
         [false, true, true, true, true, true, true],
         Duration::hours(12).to_std().unwrap(),
         Berlin,
     );
-    let ttnr: Vec<DateTime<Utc>> = weekly.next_runs(9).unwrap();
+    let ttnr: Vec<DateTime<Utc>> = weekly.next_runs(&clock, 9).unwrap();
 
     let expected_ttnr_local: Vec<DateTime<Utc>> = [
         "2023-03-24T12:00:00+01:00",
@@ -96,13 +100,13 @@ fn it_works_local_dst_change_spring() {
 
 #[test]
 fn it_works_local_dst_change_autumn() {
-    set_start_time(DST_AUTUMN_LOCAL);
+    let clock = FixedClock::new(dt_parse(DST_AUTUMN_LOCAL));
     let weekly = Weekly::new(
         [false, true, true, true, true, true, true],
         Duration::hours(12).to_std().unwrap(),
         Berlin,
     );
-    let ttnr: Vec<DateTime<Utc>> = weekly.next_runs(9).unwrap();
+    let ttnr: Vec<DateTime<Utc>> = weekly.next_runs(&clock, 9).unwrap();
 
     let expected_ttnr_local: Vec<DateTime<Utc>> = [
         "2023-10-27T12:00:00+02:00",
@@ -127,23 +131,23 @@ fn it_works_local_dst_change_autumn() {
 
 #[test]
 fn no_runs() {
-    set_start_time(DEFAULT_UTC);
+    let clock = FixedClock::new(dt_parse(DEFAULT_UTC));
     let weekly = Weekly::new(
         [false, false, false, false, false, false, false],
         Duration::hours(12).to_std().unwrap(),
         UTC,
     );
-    let ttnr = weekly.next_runs(9);
+    let ttnr = weekly.next_runs(&clock, 9);
 
     assert_eq!(ttnr, None);
 }
 
 #[test]
 fn oneshot_future() {
-    set_start_time(DEFAULT_UTC);
+    let clock = FixedClock::new(dt_parse(DEFAULT_UTC));
     let run_time = dt_parse(DEFAULT_UTC) + Duration::hours(1);
     let oneshot = Oneshot::new(run_time);
-    let next_runs: Vec<DateTime<Utc>> = oneshot.next_runs(1).unwrap();
+    let next_runs: Vec<DateTime<Utc>> = oneshot.next_runs(&clock, 1).unwrap();
 
     assert_eq!(next_runs.len(), 1);
     assert_eq!(next_runs[0], run_time);
@@ -151,19 +155,19 @@ fn oneshot_future() {
 
 #[test]
 fn oneshot_past() {
-    set_start_time(DEFAULT_UTC);
+    let clock = FixedClock::new(dt_parse(DEFAULT_UTC));
     let run_time = dt_parse(DEFAULT_UTC) - Duration::hours(1);
     let oneshot = Oneshot::new(run_time);
-    let next_runs = oneshot.next_runs(1);
+    let next_runs = oneshot.next_runs(&clock, 1);
 
     assert_eq!(next_runs, None);
 }
 
 #[test]
 fn interval() {
-    set_start_time(DEFAULT_UTC);
+    let clock = FixedClock::new(dt_parse(DEFAULT_UTC));
     let interval = Interval::new(Duration::seconds(1).to_std().unwrap());
-    let next_runs = interval.next_runs(5).unwrap();
+    let next_runs = interval.next_runs(&clock, 5).unwrap();
 
     let expected_next_runs: Vec<DateTime<Local>> = [
         "2023-01-01T00:00:01Z",
@@ -181,7 +185,6 @@ fn interval() {
 
 #[test]
 fn interval_from_json() {
-    set_start_time(DEFAULT_UTC);
     let interval = Interval::new(Duration::seconds(1).to_std().unwrap());
     let _j = serde_json::to_string(&interval).unwrap();
 
@@ -193,3 +196,522 @@ fn interval_from_json() {
     )
     .unwrap();
 }
+
+#[test]
+fn trigger_set_preview() {
+    let friday_evening = dt_parse("2023-01-06T18:00:00Z");
+    let triggers = triggerSet![
+        Oneshot::new(friday_evening),
+        Weekly::new(
+            [true, false, false, false, false, true, false],
+            Duration::hours(9).to_std().unwrap(),
+            UTC,
+        )
+    ];
+
+    let fires: Vec<(String, DateTime<Utc>)> = triggers
+        .preview(friday_evening, dt_parse("2023-01-09T08:00:00Z"))
+        .into_iter()
+        .map(|(trigger, at)| (trigger.hash(), at))
+        .collect();
+    assert_eq!(
+        fires,
+        vec![
+            (
+                Oneshot::new(friday_evening).hash(),
+                dt_parse("2023-01-06T18:00:00Z")
+            ),
+            (
+                triggers.iter().last().unwrap().hash(),
+                dt_parse("2023-01-07T09:00:00Z")
+            ),
+        ]
+    );
+}
+
+#[test]
+fn occurrences_are_lazy_and_pure() {
+    let oneshot = Oneshot::new(dt_parse(DEFAULT_UTC));
+    assert_eq!(
+        oneshot
+            .occurrences(dt_parse(DEFAULT_UTC) - Duration::seconds(1))
+            .collect::<Vec<_>>(),
+        vec![dt_parse(DEFAULT_UTC)]
+    );
+    assert_eq!(oneshot.next_after(dt_parse(DEFAULT_UTC)), None);
+
+    let interval: Box<dyn Trigger> = Box::new(Interval::new(std::time::Duration::from_secs(60)));
+    assert_eq!(
+        interval.occurrences(dt_parse(DEFAULT_UTC)).nth(1_000_000),
+        Some(dt_parse("2024-11-25T10:41:00Z"))
+    );
+}
+
+#[test]
+fn monthly_missing_days() {
+    let nine = std::time::Duration::from_secs(9 * 3600);
+    let last_day = Monthly::new(MonthDay::Day(31), nine, UTC).unwrap();
+    assert_eq!(
+        fires(&last_day, "2023-01-15T00:00:00Z", 3),
+        [
+            "2023-01-31T09:00:00+00:00",
+            "2023-03-31T09:00:00+00:00",
+            "2023-05-31T09:00:00+00:00",
+        ]
+    );
+    assert_eq!(
+        fires(
+            &last_day.with_missing_day(MissingDay::Clamp),
+            "2023-01-15T00:00:00Z",
+            3
+        ),
+        [
+            "2023-01-31T09:00:00+00:00",
+            "2023-02-28T09:00:00+00:00",
+            "2023-03-31T09:00:00+00:00",
+        ]
+    );
+
+    let fifth_friday = Monthly::new(MonthDay::NthWeekday(5, Weekday::Fri), nine, UTC).unwrap();
+    assert_eq!(
+        fires(&fifth_friday, DEFAULT_UTC, 4),
+        [
+            "2023-03-31T09:00:00+00:00",
+            "2023-06-30T09:00:00+00:00",
+            "2023-09-29T09:00:00+00:00",
+            "2023-12-29T09:00:00+00:00",
+        ]
+    );
+    assert_eq!(
+        fires(
+            &fifth_friday.with_missing_day(MissingDay::Clamp),
+            DEFAULT_UTC,
+            2
+        ),
+        ["2023-01-27T09:00:00+00:00", "2023-02-24T09:00:00+00:00"]
+    );
+}
+
+#[test]
+fn invalid_month_days() {
+    let nine = std::time::Duration::from_secs(9 * 3600);
+    for day in [
+        MonthDay::Day(0),
+        MonthDay::Day(32),
+        MonthDay::NthWeekday(0, Weekday::Mon),
+        MonthDay::NthWeekday(6, Weekday::Mon),
+    ] {
+        assert_eq!(
+            Monthly::new(day, nine, UTC).unwrap_err(),
+            MonthDayError::Day(day)
+        );
+    }
+    assert_eq!(
+        Yearly::new(13, MonthDay::Day(1), nine, UTC).unwrap_err(),
+        MonthDayError::Month(13)
+    );
+    assert_eq!(
+        Yearly::new(4, MonthDay::Day(31), nine, UTC)
+            .unwrap_err()
+            .to_string(),
+        "Day(31) is not a day of the month"
+    );
+    assert!(Yearly::new(2, MonthDay::Day(30), nine, UTC).is_err());
+
+    let invalid = [
+        r#"{"type":"Monthly","day":{"Day":32},"time":{"secs":0,"nanos":0},"tz":"UTC"}"#,
+        r#"{"type":"Monthly","day":{"NthWeekday":[6,"Mon"]},"time":{"secs":0,"nanos":0},"tz":"UTC"}"#,
+        r#"{"type":"Yearly","month":13,"day":"Last","time":{"secs":0,"nanos":0},"tz":"UTC"}"#,
+        r#"{"type":"Yearly","month":2,"day":{"Day":30},"time":{"secs":0,"nanos":0},"tz":"UTC"}"#,
+    ];
+    for json in invalid {
+        assert!(
+            serde_json::from_str::<Box<dyn Trigger>>(json).is_err(),
+            "{json}"
+        );
+    }
+    let valid = r#"{"type":"Yearly","month":2,"day":{"Day":29},"time":{"secs":0,"nanos":0},"tz":"UTC","missing_day":"Clamp"}"#;
+    let parsed = serde_json::from_str::<Box<dyn Trigger>>(valid).unwrap();
+    assert_eq!(serde_json::to_string(&parsed).unwrap(), valid);
+}
+
+#[test]
+fn monthly_business_days() {
+    let six_pm = std::time::Duration::from_secs(18 * 3600);
+    assert_eq!(
+        fires(
+            &Monthly::new(MonthDay::LastBusinessDay, six_pm, Berlin).unwrap(),
+            "2023-09-01T00:00:00Z",
+            4
+        ),
+        [
+            "2023-09-29T16:00:00+00:00",
+            "2023-10-31T17:00:00+00:00",
+            "2023-11-30T17:00:00+00:00",
+            "2023-12-29T17:00:00+00:00",
+        ]
+    );
+    assert_eq!(
+        fires(
+            &Monthly::new(MonthDay::NthWeekday(1, Weekday::Mon), six_pm, UTC).unwrap(),
+            DEFAULT_UTC,
+            3
+        ),
+        [
+            "2023-01-02T18:00:00+00:00",
+            "2023-02-06T18:00:00+00:00",
+            "2023-03-06T18:00:00+00:00",
+        ]
+    );
+    assert_eq!(
+        fires(
+            &Monthly::new(MonthDay::LastWeekday(Weekday::Sun), six_pm, UTC).unwrap(),
+            DEFAULT_UTC,
+            2
+        ),
+        ["2023-01-29T18:00:00+00:00", "2023-02-26T18:00:00+00:00"]
+    );
+}
+
+#[test]
+fn yearly() {
+    let six_pm = std::time::Duration::from_secs(18 * 3600);
+    let march_31 = Yearly::new(3, MonthDay::Day(31), six_pm, Berlin).unwrap();
+    assert_eq!(
+        fires(&march_31, "2023-03-31T16:00:00Z", 2),
+        ["2024-03-31T16:00:00+00:00", "2025-03-31T16:00:00+00:00"]
+    );
+
+    let leap_day = Yearly::new(2, MonthDay::Day(29), six_pm, UTC).unwrap();
+    assert_eq!(
+        fires(&leap_day, DEFAULT_UTC, 2),
+        ["2024-02-29T18:00:00+00:00", "2028-02-29T18:00:00+00:00"]
+    );
+    assert_eq!(
+        fires(
+            &leap_day.with_missing_day(MissingDay::Clamp),
+            DEFAULT_UTC,
+            3
+        ),
+        [
+            "2023-02-28T18:00:00+00:00",
+            "2024-02-29T18:00:00+00:00",
+            "2025-02-28T18:00:00+00:00",
+        ]
+    );
+
+    let trigger: Box<dyn Trigger> = Box::new(
+        Yearly::new(12, MonthDay::LastBusinessDay, six_pm, Berlin)
+            .unwrap()
+            .with_missing_day(MissingDay::Clamp),
+    );
+    let json = serde_json::to_string(&trigger).unwrap();
+    assert_eq!(
+        json,
+        r#"{"type":"Yearly","month":12,"day":"LastBusinessDay","time":{"secs":64800,"nanos":0},"tz":"Europe/Berlin","missing_day":"Clamp"}"#
+    );
+    let parsed: Box<dyn Trigger> = serde_json::from_str(&json).unwrap();
+    assert!(*parsed == *trigger);
+}
+
+fn daily(hours: u64, minutes: u64, tz: chrono_tz::Tz) -> Weekly {
+    let time = std::time::Duration::from_secs(hours * 3600 + minutes * 60);
+    Weekly::new([true; 7], time, tz)
+}
+
+#[test]
+fn dst_gaps() {
+    // Berlin springs forward from 02:00 to 03:00 on 2023-03-26
+    let berlin = daily(2, 30, Berlin);
+    let from = "2023-03-25T12:00:00Z";
+    assert_eq!(
+        fires(&berlin, from, 2),
+        ["2023-03-26T01:30:00+00:00", "2023-03-27T00:30:00+00:00"]
+    );
+    assert_eq!(
+        fires(&berlin.clone().with_dst_gap(DstGap::Skip), from, 1),
+        ["2023-03-27T00:30:00+00:00"]
+    );
+    assert_eq!(
+        fires(&berlin.with_dst_gap(DstGap::Transition), from, 1),
+        ["2023-03-26T01:00:00+00:00"]
+    );
+
+    // New York springs forward from 02:00 to 03:00 on 2023-03-12
+    let new_york = daily(2, 30, New_York);
+    let from = "2023-03-11T12:00:00Z";
+    assert_eq!(fires(&new_york, from, 1), ["2023-03-12T07:30:00+00:00"]);
+    assert_eq!(
+        fires(&new_york.with_dst_gap(DstGap::Transition), from, 1),
+        ["2023-03-12T07:00:00+00:00"]
+    );
+
+    // Lord Howe Island springs forward by half an hour, from 02:00 to 02:30, on 2023-10-01
+    let lord_howe = daily(2, 15, Lord_Howe);
+    let from = "2023-09-30T00:00:00Z";
+    assert_eq!(fires(&lord_howe, from, 1), ["2023-09-30T15:45:00+00:00"]);
+    assert_eq!(
+        fires(&lord_howe.clone().with_dst_gap(DstGap::Transition), from, 1),
+        ["2023-09-30T15:30:00+00:00"]
+    );
+    assert_eq!(
+        fires(&lord_howe.with_dst_gap(DstGap::Skip), from, 1),
+        ["2023-10-01T15:15:00+00:00"]
+    );
+}
+
+#[test]
+fn dst_folds() {
+    // Berlin falls back from 03:00 to 02:00 on 2023-10-29
+    let berlin = daily(2, 30, Berlin);
+    let from = "2023-10-28T12:00:00Z";
+    assert_eq!(
+        fires(&berlin, from, 2),
+        ["2023-10-29T00:30:00+00:00", "2023-10-30T01:30:00+00:00"]
+    );
+    assert_eq!(
+        fires(&berlin.clone().with_dst_fold(DstFold::Second), from, 1),
+        ["2023-10-29T01:30:00+00:00"]
+    );
+    assert_eq!(
+        fires(&berlin.with_dst_fold(DstFold::Both), from, 3),
+        [
+            "2023-10-29T00:30:00+00:00",
+            "2023-10-29T01:30:00+00:00",
+            "2023-10-30T01:30:00+00:00",
+        ]
+    );
+
+    // New York falls back from 02:00 to 01:00 on 2023-11-05
+    let new_york = daily(1, 30, New_York);
+    let from = "2023-11-04T12:00:00Z";
+    assert_eq!(fires(&new_york, from, 1), ["2023-11-05T05:30:00+00:00"]);
+    assert_eq!(
+        fires(&new_york.with_dst_fold(DstFold::Second), from, 1),
+        ["2023-11-05T06:30:00+00:00"]
+    );
+
+    // Lord Howe Island falls back by half an hour, from 02:00 to 01:30, on 2023-04-02
+    let lord_howe = Monthly::new(
+        MonthDay::Day(2),
+        std::time::Duration::from_secs(6300),
+        Lord_Howe,
+    )
+    .unwrap()
+    .with_dst_fold(DstFold::Both);
+    assert_eq!(
+        fires(&lord_howe, "2023-03-15T00:00:00Z", 3),
+        [
+            "2023-04-01T14:45:00+00:00",
+            "2023-04-01T15:15:00+00:00",
+            "2023-05-01T15:15:00+00:00",
+        ]
+    );
+}
+
+#[test]
+fn dst_policies_serialize() {
+    let weekly = daily(2, 30, Berlin)
+        .with_dst_gap(DstGap::Transition)
+        .with_dst_fold(DstFold::Both);
+    let trigger: Box<dyn Trigger> = Box::new(weekly);
+    let json = serde_json::to_string(&trigger).unwrap();
+    assert!(json.contains(r#""dst_gap":"Transition","dst_fold":"Both""#));
+    let parsed: Box<dyn Trigger> = serde_json::from_str(&json).unwrap();
+    assert!(*parsed == *trigger);
+
+    let default = serde_json::to_string(&(Box::new(daily(2, 30, Berlin)) as Box<dyn Trigger>));
+    assert!(!default.unwrap().contains("dst_"));
+}
+
+#[test]
+fn weekly_times() {
+    let at = |hours: u64, minutes: u64| std::time::Duration::from_secs(hours * 3600 + minutes * 60);
+    let workdays = vec![at(17, 30), at(8, 0)];
+    let weekly = Weekly::from_times(
+        [
+            workdays.clone(),
+            workdays.clone(),
+            workdays.clone(),
+            workdays.clone(),
+            workdays,
+            vec![at(10, 0)],
+            vec![],
+        ],
+        Berlin,
+    )
+    .unwrap();
+    assert_eq!(weekly.times(Weekday::Mon), [at(8, 0), at(17, 30)]);
+    // Thursday 2023-06-01
+    assert_eq!(
+        fires(&weekly, "2023-06-01T12:00:00Z", 5),
+        [
+            "2023-06-01T15:30:00+00:00",
+            "2023-06-02T06:00:00+00:00",
+            "2023-06-02T15:30:00+00:00",
+            "2023-06-03T08:00:00+00:00",
+            "2023-06-05T06:00:00+00:00",
+        ]
+    );
+
+    let trigger: Box<dyn Trigger> = Box::new(weekly);
+    let json = serde_json::to_string(&trigger).unwrap();
+    assert!(json.starts_with(
+        r#"{"type":"Weekly","times":[[{"secs":28800,"nanos":0},{"secs":63000,"nanos":0}],"#
+    ));
+    let parsed: Box<dyn Trigger> = serde_json::from_str(&json).unwrap();
+    assert!(*parsed == *trigger);
+
+    let mut times: [Vec<_>; 7] = Default::default();
+    times[2] = vec![at(9, 0), at(24, 0)];
+    let error = Weekly::from_times(times, UTC).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "time 86400s on Wed is not under 24 hours"
+    );
+    let invalid =
+        r#"{"type":"Weekly","times":[[],[],[{"secs":86400,"nanos":0}],[],[],[],[]],"tz":"UTC"}"#;
+    assert!(serde_json::from_str::<Box<dyn Trigger>>(invalid).is_err());
+}
+
+#[test]
+fn interval_anchor_and_end() {
+    let quarter_hour = std::time::Duration::from_secs(15 * 60);
+    let anchored = Interval::new(quarter_hour).with_anchor(dt_parse("2023-01-01T00:00:00Z"));
+    assert_eq!(
+        fires(&anchored, "2023-06-01T10:07:12Z", 3),
+        [
+            "2023-06-01T10:15:00+00:00",
+            "2023-06-01T10:30:00+00:00",
+            "2023-06-01T10:45:00+00:00",
+        ]
+    );
+    // a future anchor is where the runs start
+    assert_eq!(
+        fires(&anchored, "2022-12-31T12:00:00Z", 2),
+        ["2023-01-01T00:00:00+00:00", "2023-01-01T00:15:00+00:00"]
+    );
+
+    let ending = anchored.with_end(dt_parse("2023-06-01T10:30:00Z"));
+    assert_eq!(
+        fires(&ending, "2023-06-01T10:07:12Z", 3),
+        ["2023-06-01T10:15:00+00:00", "2023-06-01T10:30:00+00:00"]
+    );
+
+    // an anchor is stored where the unused `last_run` used to be, `anchor` is read as well
+    let stored = r#"{"interval":{"secs":900,"nanos":0},"last_run":"2023-01-01T00:00:00Z","end":"2023-06-01T10:30:00Z"}"#;
+    assert_eq!(serde_json::to_string(&ending).unwrap(), stored);
+    for json in [stored, &stored.replace("last_run", "anchor")] {
+        let parsed: Interval = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            fires(&parsed, "2023-06-01T10:07:12Z", 3),
+            ["2023-06-01T10:15:00+00:00", "2023-06-01T10:30:00+00:00"]
+        );
+    }
+}
+
+#[test]
+fn interval_jitter() {
+    let minute = std::time::Duration::from_secs(60);
+    let jittered = Interval::new(minute)
+        .with_anchor(dt_parse(DEFAULT_UTC))
+        .with_jitter(std::time::Duration::from_secs(10));
+    let from = dt_parse(DEFAULT_UTC);
+    let runs: Vec<_> = jittered.occurrences(from).take(100).collect();
+    for (n, run) in runs.iter().enumerate() {
+        let slot = from + Duration::minutes(n as i64);
+        assert!(*run >= slot && *run < slot + Duration::seconds(10), "{run}");
+    }
+    assert!(runs.iter().any(|run| run.timestamp() % 60 != 0));
+
+    // the same run is always jittered the same way, also after a round trip through JSON
+    assert_eq!(jittered.next_after(runs[4]), Some(runs[5]));
+    assert_eq!(
+        jittered.next_after(runs[5] - Duration::seconds(20)),
+        Some(runs[5])
+    );
+    let parsed: Interval =
+        serde_json::from_str(&serde_json::to_string(&jittered).unwrap()).unwrap();
+    assert_eq!(parsed.occurrences(from).take(100).collect::<Vec<_>>(), runs);
+
+    // the end applies to the slot, however late its run
+    let ending = jittered.clone().with_end(from + Duration::minutes(99));
+    assert_eq!(ending.occurrences(from).collect::<Vec<_>>(), runs);
+}
+
+#[test]
+fn unanchored_interval_jitter_keeps_period() {
+    let jittered = Interval::new(std::time::Duration::from_secs(60))
+        .with_jitter(std::time::Duration::from_secs(30));
+    let from = dt_parse(DEFAULT_UTC);
+    let runs: Vec<_> = jittered.occurrences(from).take(100).collect();
+    // jittered from whole minutes since the epoch, one minute apart however late each run is
+    let slots: Vec<_> = runs
+        .iter()
+        .map(|run| {
+            assert!(run.timestamp() % 60 < 30, "{run}");
+            run.timestamp() / 60
+        })
+        .collect();
+    assert!(slots.windows(2).all(|pair| pair[1] == pair[0] + 1));
+}
+
+#[test]
+fn bounded_triggers() {
+    let hourly =
+        || Interval::new(std::time::Duration::from_secs(3600)).with_anchor(dt_parse(DEFAULT_UTC));
+    let from = "2023-01-01T00:30:00Z";
+
+    let between = Between::new(
+        hourly(),
+        dt_parse("2023-01-01T03:00:00Z"),
+        dt_parse("2023-01-01T05:00:00Z"),
+    );
+    assert_eq!(
+        fires(&between, from, 5),
+        [
+            "2023-01-01T03:00:00+00:00",
+            "2023-01-01T04:00:00+00:00",
+            "2023-01-01T05:00:00+00:00",
+        ]
+    );
+
+    let until = Until::new(hourly(), dt_parse("2023-01-01T03:00:00Z"));
+    assert_eq!(
+        fires(&until, from, 5),
+        ["2023-01-01T01:00:00+00:00", "2023-01-01T02:00:00+00:00"]
+    );
+
+    let limit = Limit::new(hourly(), 3, dt_parse(from));
+    assert_eq!(
+        fires(&limit, from, 5),
+        [
+            "2023-01-01T01:00:00+00:00",
+            "2023-01-01T02:00:00+00:00",
+            "2023-01-01T03:00:00+00:00",
+        ]
+    );
+    // counted from `since`, not from wherever the fires are asked for
+    assert_eq!(
+        fires(&limit, "2023-01-01T02:30:00Z", 5),
+        ["2023-01-01T03:00:00+00:00"]
+    );
+
+    // walking all fires doesn't count them again from `since` each time
+    let every_second = Interval::new(std::time::Duration::from_secs(1)).with_anchor(dt_parse(from));
+    let many = Limit::new(every_second, 100_000, dt_parse(from));
+    assert_eq!(many.occurrences(dt_parse(from)).count(), 100_000);
+    assert_eq!(fires(&many, from, 1), ["2023-01-01T00:30:01+00:00"]);
+
+    let nested: Box<dyn Trigger> = Box::new(Limit::new(until, 1, dt_parse(from)));
+    let json = serde_json::to_string(&nested).unwrap();
+    assert_eq!(
+        json,
+        r#"{"type":"Limit","inner":{"type":"Until","inner":{"type":"Interval","interval":{"secs":3600,"nanos":0},"last_run":"2023-01-01T00:00:00Z"},"until":"2023-01-01T03:00:00Z"},"max_runs":1,"since":"2023-01-01T00:30:00Z"}"#
+    );
+    let parsed: Box<dyn Trigger> = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        fires(parsed.as_ref(), from, 5),
+        ["2023-01-01T01:00:00+00:00"]
+    );
+}
@@ -0,0 +1,134 @@
+use super::{Calendars, Trigger};
+use crate::error::SchedulerError;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+/// Fires like `inner`, but only within `[not_before, not_after]`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Between {
+    inner: Box<dyn Trigger>,
+    not_before: DateTime<Utc>,
+    not_after: DateTime<Utc>,
+}
+
+impl Between {
+    pub fn new(
+        inner: impl Trigger + 'static,
+        not_before: DateTime<Utc>,
+        not_after: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            inner: Box::new(inner),
+            not_before,
+            not_after,
+        }
+    }
+}
+
+#[typetag::serde]
+impl Trigger for Between {
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let after = after.max(self.not_before - Duration::nanoseconds(1));
+        self.inner
+            .next_after(after)
+            .filter(|next| *next <= self.not_after)
+    }
+
+    fn bind_calendars(&self, calendars: &Calendars) -> Result<(), SchedulerError> {
+        self.inner.bind_calendars(calendars)
+    }
+
+    fn hash(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+/// Fires like `inner` for its first `max_runs` fires after `since`. Manual runs don't count.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Limit {
+    inner: Box<dyn Trigger>,
+    max_runs: usize,
+    since: DateTime<Utc>,
+    // the latest fire handed out and how many fires after `since` it is, so the next call
+    // walks on from there instead of from `since`
+    #[serde(skip)]
+    cursor: Mutex<Option<(usize, DateTime<Utc>)>>,
+}
+
+impl Limit {
+    pub fn new(inner: impl Trigger + 'static, max_runs: usize, since: DateTime<Utc>) -> Self {
+        Self {
+            inner: Box::new(inner),
+            max_runs,
+            since,
+            cursor: Mutex::new(None),
+        }
+    }
+}
+
+#[typetag::serde]
+impl Trigger for Limit {
+    // counts from `since` rather than from the runs, so the count survives restarts and
+    // previews don't use up runs; the cursor only saves walking over the same fires again
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut cursor = self.cursor.lock().unwrap();
+        let (mut count, mut from) = match *cursor {
+            Some((count, fire)) if fire <= after => (count, fire),
+            _ => (0, self.since),
+        };
+        while count < self.max_runs {
+            let next = self.inner.next_after(from)?;
+            count += 1;
+            if next > after {
+                if cursor.is_none_or(|(_, fire)| fire < next) {
+                    *cursor = Some((count, next));
+                }
+                return Some(next);
+            }
+            from = next;
+        }
+        None
+    }
+
+    fn bind_calendars(&self, calendars: &Calendars) -> Result<(), SchedulerError> {
+        self.inner.bind_calendars(calendars)
+    }
+
+    fn hash(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+/// Fires like `inner` until just before `until`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Until {
+    inner: Box<dyn Trigger>,
+    until: DateTime<Utc>,
+}
+
+impl Until {
+    pub fn new(inner: impl Trigger + 'static, until: DateTime<Utc>) -> Self {
+        Self {
+            inner: Box::new(inner),
+            until,
+        }
+    }
+}
+
+#[typetag::serde]
+impl Trigger for Until {
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.inner
+            .next_after(after)
+            .filter(|next| *next < self.until)
+    }
+
+    fn bind_calendars(&self, calendars: &Calendars) -> Result<(), SchedulerError> {
+        self.inner.bind_calendars(calendars)
+    }
+
+    fn hash(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
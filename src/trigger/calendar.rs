@@ -0,0 +1,122 @@
+use super::dst::{self, DstFold, DstGap};
+use super::weekly::Tz;
+use crate::error::SchedulerError;
+use crate::job::Job;
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// how many back to back exclusions to follow before giving up on finding an allowed instant
+const MAX_EXCLUSIONS_AHEAD: usize = 1000;
+
+/// A period during which a [`Calendar`] excludes runs, in the calendar's time zone.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Exclusion {
+    /// A single day.
+    Date(NaiveDate),
+    /// The days from the first to the second, both included.
+    Range(NaiveDate, NaiveDate),
+    /// The same day every year, as month and day, e.g. `Annual(12, 25)`.
+    Annual(u32, u32),
+    /// The same window every day, from the first time of day up to the second. Wraps past
+    /// midnight if the second is earlier.
+    Blackout(NaiveTime, NaiveTime),
+}
+
+/// Holidays and blackout windows that [`Exclude`](super::Exclude) keeps runs out of.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Calendar {
+    tz: Tz,
+    exclusions: Vec<Exclusion>,
+}
+
+impl Calendar {
+    pub fn new(tz: chrono_tz::Tz) -> Self {
+        Self {
+            tz: Tz(tz),
+            exclusions: Vec::new(),
+        }
+    }
+
+    pub fn with_exclusion(mut self, exclusion: Exclusion) -> Self {
+        self.exclusions.push(exclusion);
+        self
+    }
+
+    pub fn excludes(&self, at: DateTime<Utc>) -> bool {
+        self.excluded_until(at).is_some()
+    }
+
+    /// The first instant from `at` on that isn't excluded, or `None` if there's none in sight.
+    pub fn next_allowed(&self, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut at = at;
+        for _ in 0..MAX_EXCLUSIONS_AHEAD {
+            match self.excluded_until(at) {
+                Some(end) => at = end?,
+                None => return Some(at),
+            }
+        }
+        None
+    }
+
+    // end of an exclusion covering `at`, `Some(None)` if that end can't be told
+    fn excluded_until(&self, at: DateTime<Utc>) -> Option<Option<DateTime<Utc>>> {
+        let local = at.with_timezone(&self.tz.0).naive_local();
+        let (date, time) = (local.date(), local.time());
+        let next_day = date.checked_add_days(Days::new(1));
+        self.exclusions.iter().find_map(|exclusion| {
+            let (end_date, end_time) = match *exclusion {
+                Exclusion::Date(day) if day == date => (next_day, NaiveTime::MIN),
+                Exclusion::Range(from, to) if (from..=to).contains(&date) => {
+                    (to.checked_add_days(Days::new(1)), NaiveTime::MIN)
+                }
+                Exclusion::Annual(month, day) if (date.month(), date.day()) == (month, day) => {
+                    (next_day, NaiveTime::MIN)
+                }
+                Exclusion::Blackout(from, to) if from <= time && (time < to || to <= from) => {
+                    (if to <= from { next_day } else { Some(date) }, to)
+                }
+                Exclusion::Blackout(from, to) if time < to && to <= from => (Some(date), to),
+                _ => return None,
+            };
+            Some(end_date.and_then(|end_date| {
+                dst::resolve(
+                    self.tz.0,
+                    end_date.and_time(end_time),
+                    DstGap::Transition,
+                    DstFold::Both,
+                )
+                .find(|end| *end > at)
+            }))
+        })
+    }
+}
+
+/// Calendars by name, shared by the [`Exclude`](super::Exclude) triggers of a scheduler's jobs.
+#[derive(Clone, Debug, Default)]
+pub struct Calendars {
+    calendars: HashMap<String, Arc<Calendar>>,
+}
+
+impl Calendars {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, name: &str, calendar: Calendar) -> &mut Self {
+        self.calendars.insert(name.to_string(), Arc::new(calendar));
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Arc<Calendar>> {
+        self.calendars.get(name)
+    }
+
+    pub fn resolve(&self, job: &Job) -> Result<(), SchedulerError> {
+        job.triggers()
+            .iter()
+            .try_for_each(|trigger| trigger.bind_calendars(self))
+    }
+}
@@ -0,0 +1,517 @@
+use super::dst::{self, DstFold, DstGap};
+use super::{weekly::Tz, Trigger};
+use chrono::{
+    DateTime, Datelike, Duration, Months, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone,
+    Timelike, Utc, Weekday,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// how far ahead to look for a matching date before giving up on an expression
+const MAX_YEARS_AHEAD: i32 = 28;
+
+// more than any DST change shifts local time by
+const DST_WINDOW: Duration = Duration::hours(3);
+
+const MONTH_NAMES: &[&str] = &[
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+const WEEKDAY_NAMES: &[&str] = &["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    expression: String,
+    reason: String,
+}
+
+impl ParseError {
+    fn new(expression: &str, reason: impl Into<String>) -> Self {
+        Self {
+            expression: expression.to_string(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid cron expression \"{}\": {}",
+            self.expression, self.reason
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+struct Field {
+    name: &'static str,
+    min: u32,
+    max: u32,
+    names: &'static [&'static str],
+    names_offset: u32,
+}
+
+const SECONDS: Field = Field {
+    name: "seconds",
+    min: 0,
+    max: 59,
+    names: &[],
+    names_offset: 0,
+};
+const MINUTES: Field = Field {
+    name: "minutes",
+    min: 0,
+    max: 59,
+    names: &[],
+    names_offset: 0,
+};
+const HOURS: Field = Field {
+    name: "hours",
+    min: 0,
+    max: 23,
+    names: &[],
+    names_offset: 0,
+};
+const DAYS_OF_MONTH: Field = Field {
+    name: "day of month",
+    min: 1,
+    max: 31,
+    names: &[],
+    names_offset: 0,
+};
+const MONTHS: Field = Field {
+    name: "month",
+    min: 1,
+    max: 12,
+    names: MONTH_NAMES,
+    names_offset: 1,
+};
+const DAYS_OF_WEEK: Field = Field {
+    name: "day of week",
+    min: 0,
+    max: 7,
+    names: WEEKDAY_NAMES,
+    names_offset: 0,
+};
+
+impl Field {
+    fn value(&self, token: &str) -> Result<u32, String> {
+        let value = match token.parse::<u32>() {
+            Ok(value) => value,
+            Err(_) => self
+                .names
+                .iter()
+                .position(|name| name.eq_ignore_ascii_case(token))
+                .map(|i| i as u32 + self.names_offset)
+                .ok_or_else(|| format!("invalid {} value \"{}\"", self.name, token))?,
+        };
+        match (self.min..=self.max).contains(&value) {
+            true => Ok(value),
+            false => Err(format!(
+                "{} value {} out of range {}-{}",
+                self.name, value, self.min, self.max
+            )),
+        }
+    }
+
+    // parses a single list element (`*`, `a`, `a-b`, each optionally with `/step`) into `mask`
+    fn parse_part(&self, part: &str, mask: &mut u64) -> Result<(), String> {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => match step.parse::<u32>() {
+                Ok(step) if step > 0 => (range, Some(step)),
+                _ => return Err(format!("invalid step \"{}\" in {}", step, self.name)),
+            },
+            None => (part, None),
+        };
+
+        let (start, end) = match range {
+            "*" | "?" => (self.min, self.max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (self.value(start)?, self.value(end)?),
+                None => {
+                    let value = self.value(range)?;
+                    (value, step.map_or(value, |_| self.max))
+                }
+            },
+        };
+
+        if start > end {
+            return Err(format!("invalid {} range \"{}\"", self.name, range));
+        }
+
+        for value in (start..=end).step_by(step.unwrap_or(1) as usize) {
+            *mask |= 1 << value;
+        }
+        Ok(())
+    }
+
+    fn parse_mask(&self, expr: &str) -> Result<u64, String> {
+        let mut mask = 0;
+        for part in expr.split(',') {
+            self.parse_part(part, &mut mask)?;
+        }
+        Ok(mask)
+    }
+}
+
+fn next_bit(mask: u64, from: u32) -> Option<u32> {
+    if from >= 64 {
+        return None;
+    }
+    match mask & (u64::MAX << from) {
+        0 => None,
+        masked => Some(masked.trailing_zeros()),
+    }
+}
+
+fn last_day_of_month(date: NaiveDate) -> u32 {
+    let (year, month) = match date.month() {
+        12 => (date.year() + 1, 1),
+        month => (date.year(), month + 1),
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|first| first.pred_opt())
+        .map_or(31, |last| last.day())
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct DaysOfMonth {
+    // starts with `*` or `?`, vixie-cron's DOM_STAR
+    star: bool,
+    days: u64,
+    last: Vec<u32>,
+    last_weekday: bool,
+    nearest_weekday: Vec<u32>,
+}
+
+impl DaysOfMonth {
+    fn parse(expr: &str) -> Result<Self, String> {
+        let mut days = Self {
+            star: expr.starts_with('*') || expr.starts_with('?'),
+            ..Default::default()
+        };
+        for part in expr.split(',') {
+            if part == "L" {
+                days.last.push(0);
+            } else if let Some(offset) = part.strip_prefix("L-") {
+                match offset.parse::<u32>() {
+                    Ok(offset) if offset < 31 => days.last.push(offset),
+                    _ => return Err(format!("invalid last day offset \"{}\"", part)),
+                }
+            } else if part == "LW" {
+                days.last_weekday = true;
+            } else if let Some(day) = part.strip_suffix('W') {
+                days.nearest_weekday.push(DAYS_OF_MONTH.value(day)?);
+            } else {
+                DAYS_OF_MONTH.parse_part(part, &mut days.days)?;
+            }
+        }
+        Ok(days)
+    }
+
+    fn matches(&self, date: NaiveDate) -> bool {
+        let day = date.day();
+        let last_day = last_day_of_month(date);
+
+        self.days & (1 << day) != 0
+            || self.last.iter().any(|offset| day + offset == last_day)
+            || (self.last_weekday && Self::last_weekday(date, last_day) == Some(day))
+            || self
+                .nearest_weekday
+                .iter()
+                .any(|target| Self::nearest_weekday(date, *target, last_day) == Some(day))
+    }
+
+    fn last_weekday(date: NaiveDate, last_day: u32) -> Option<u32> {
+        Some(match date.with_day(last_day)?.weekday() {
+            Weekday::Sat => last_day - 1,
+            Weekday::Sun => last_day - 2,
+            _ => last_day,
+        })
+    }
+
+    fn nearest_weekday(date: NaiveDate, target: u32, last_day: u32) -> Option<u32> {
+        if target > last_day {
+            return None;
+        }
+        let target_date = date.with_day(target)?;
+        Some(match target_date.weekday() {
+            Weekday::Sat if target == 1 => target + 2,
+            Weekday::Sat => target - 1,
+            Weekday::Sun if target == last_day => target - 2,
+            Weekday::Sun => target + 1,
+            _ => target,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct DaysOfWeek {
+    // starts with `*` or `?`, vixie-cron's DOW_STAR
+    star: bool,
+    days: u64,
+    last: Vec<u32>,
+    nth: Vec<(u32, u32)>,
+}
+
+impl DaysOfWeek {
+    fn parse(expr: &str) -> Result<Self, String> {
+        let mut days = Self {
+            star: expr.starts_with('*') || expr.starts_with('?'),
+            ..Default::default()
+        };
+        for part in expr.split(',') {
+            if part == "L" {
+                days.days |= 1 << 6;
+            } else if let Some((weekday, nth)) = part.split_once('#') {
+                let weekday = DAYS_OF_WEEK.value(weekday)? % 7;
+                match nth.parse::<u32>() {
+                    Ok(nth) if (1..=5).contains(&nth) => days.nth.push((weekday, nth)),
+                    _ => return Err(format!("invalid weekday occurrence \"{}\"", part)),
+                }
+            } else if let Some(weekday) = part.strip_suffix('L') {
+                days.last.push(DAYS_OF_WEEK.value(weekday)? % 7);
+            } else {
+                DAYS_OF_WEEK.parse_part(part, &mut days.days)?;
+            }
+        }
+        // both 0 and 7 mean sunday
+        if days.days & (1 << 7) != 0 {
+            days.days = (days.days | 1) & !(1 << 7);
+        }
+        Ok(days)
+    }
+
+    fn matches(&self, date: NaiveDate) -> bool {
+        let weekday = date.weekday().num_days_from_sunday();
+        let day = date.day();
+
+        self.days & (1 << weekday) != 0
+            || self
+                .last
+                .iter()
+                .any(|last| *last == weekday && day + 7 > last_day_of_month(date))
+            || self
+                .nth
+                .iter()
+                .any(|(nth_weekday, nth)| *nth_weekday == weekday && (day - 1) / 7 + 1 == *nth)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Schedule {
+    seconds: u64,
+    minutes: u64,
+    hours: u64,
+    days_of_month: DaysOfMonth,
+    months: u64,
+    days_of_week: DaysOfWeek,
+}
+
+impl Schedule {
+    fn parse(expression: &str) -> Result<Self, ParseError> {
+        let expanded = match expression.trim() {
+            "@yearly" | "@annually" => "0 0 0 1 1 *",
+            "@monthly" => "0 0 0 1 * *",
+            "@weekly" => "0 0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 0 * * *",
+            "@hourly" => "0 0 * * * *",
+            expression => expression,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let (seconds, fields) = match fields.len() {
+            5 => ("0", &fields[..]),
+            6 => (fields[0], &fields[1..]),
+            n => {
+                return Err(ParseError::new(
+                    expression,
+                    format!("expected 5 or 6 fields, got {}", n),
+                ))
+            }
+        };
+
+        let parse = || -> Result<Self, String> {
+            Ok(Self {
+                seconds: SECONDS.parse_mask(seconds)?,
+                minutes: MINUTES.parse_mask(fields[0])?,
+                hours: HOURS.parse_mask(fields[1])?,
+                days_of_month: DaysOfMonth::parse(fields[2])?,
+                months: MONTHS.parse_mask(fields[3])?,
+                days_of_week: DaysOfWeek::parse(fields[4])?,
+            })
+        };
+        parse().map_err(|reason| ParseError::new(expression, reason))
+    }
+
+    // like vixie-cron, both day fields have to match if either starts with `*`, otherwise one
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        match self.days_of_month.star || self.days_of_week.star {
+            true => self.days_of_month.matches(date) && self.days_of_week.matches(date),
+            false => self.days_of_month.matches(date) || self.days_of_week.matches(date),
+        }
+    }
+
+    // earliest time of day at or after `from` that matches the time fields
+    fn next_time(&self, from: NaiveTime) -> Option<NaiveTime> {
+        let (from_hour, from_minute, from_second) = (from.hour(), from.minute(), from.second());
+        let mut hour = next_bit(self.hours, from_hour)?;
+        loop {
+            let mut minute_from = if hour == from_hour { from_minute } else { 0 };
+            while let Some(minute) = next_bit(self.minutes, minute_from) {
+                let second_from = match hour == from_hour && minute == from_minute {
+                    true => from_second,
+                    false => 0,
+                };
+                if let Some(second) = next_bit(self.seconds, second_from) {
+                    return NaiveTime::from_hms_opt(hour, minute, second);
+                }
+                minute_from = minute + 1;
+            }
+            hour = next_bit(self.hours, hour + 1)?;
+        }
+    }
+
+    // matching local times from `start` on, in order
+    fn local_times(&self, start: NaiveDateTime) -> impl Iterator<Item = NaiveDateTime> + '_ {
+        let last_year = start.year() + MAX_YEARS_AHEAD;
+        let mut date = start.date();
+        let mut from = Some(start.time());
+        std::iter::from_fn(move || {
+            while date.year() <= last_year {
+                if self.months & (1 << date.month()) == 0 {
+                    date = NaiveDate::from_ymd_opt(date.year(), date.month(), 1)?
+                        .checked_add_months(Months::new(1))?;
+                    from = Some(NaiveTime::MIN);
+                    continue;
+                }
+                let time = from
+                    .filter(|_| self.day_matches(date))
+                    .and_then(|from| self.next_time(from));
+                if let Some(time) = time {
+                    from = NaiveTime::from_num_seconds_from_midnight_opt(
+                        time.num_seconds_from_midnight() + 1,
+                        0,
+                    );
+                    return Some(date.and_time(time));
+                }
+                date = date.succ_opt()?;
+                from = Some(NaiveTime::MIN);
+            }
+            None
+        })
+    }
+
+    fn next_after(
+        &self,
+        after: DateTime<Utc>,
+        tz: chrono_tz::Tz,
+        gap: DstGap,
+        fold: DstFold,
+    ) -> Option<DateTime<Utc>> {
+        let fires = |local: NaiveDateTime| dst::resolve(tz, local, gap, fold);
+        let local = after.with_timezone(&tz).naive_local().with_nanosecond(0)?;
+        let next = self
+            .local_times(local + Duration::seconds(1))
+            .flat_map(fires)
+            .find(|dt| *dt > after)?;
+
+        let offset = |dt: DateTime<Utc>| tz.offset_from_utc_datetime(&dt.naive_utc()).fix();
+        let near_change = |dt: DateTime<Utc>| offset(dt - DST_WINDOW) != offset(dt + DST_WINDOW);
+        if !near_change(after) && !near_change(next) {
+            return Some(next);
+        }
+        // local times run out of order around DST changes, so look at every one that could map
+        // to an earlier instant
+        let mut next = next;
+        for local in self.local_times(local - DST_WINDOW) {
+            if local > next.with_timezone(&tz).naive_local() + DST_WINDOW {
+                break;
+            }
+            if let Some(earlier) = fires(local).filter(|dt| *dt > after).min() {
+                next = next.min(earlier);
+            }
+        }
+        Some(next)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct CronSpec {
+    expression: String,
+    tz: Tz,
+    #[serde(default, skip_serializing_if = "DstGap::is_default")]
+    dst_gap: DstGap,
+    #[serde(default, skip_serializing_if = "DstFold::is_default")]
+    dst_fold: DstFold,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(try_from = "CronSpec", into = "CronSpec")]
+pub struct Cron {
+    expression: String,
+    tz: Tz,
+    schedule: Schedule,
+    dst_gap: DstGap,
+    dst_fold: DstFold,
+}
+
+impl Cron {
+    pub fn new(expression: &str, tz: chrono_tz::Tz) -> Result<Self, ParseError> {
+        Ok(Self {
+            expression: expression.to_string(),
+            tz: Tz(tz),
+            schedule: Schedule::parse(expression)?,
+            dst_gap: DstGap::default(),
+            dst_fold: DstFold::default(),
+        })
+    }
+
+    pub fn with_dst_gap(mut self, dst_gap: DstGap) -> Self {
+        self.dst_gap = dst_gap;
+        self
+    }
+
+    pub fn with_dst_fold(mut self, dst_fold: DstFold) -> Self {
+        self.dst_fold = dst_fold;
+        self
+    }
+
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+}
+
+impl TryFrom<CronSpec> for Cron {
+    type Error = ParseError;
+
+    fn try_from(spec: CronSpec) -> Result<Self, Self::Error> {
+        Ok(Self::new(&spec.expression, spec.tz.0)?
+            .with_dst_gap(spec.dst_gap)
+            .with_dst_fold(spec.dst_fold))
+    }
+}
+
+impl From<Cron> for CronSpec {
+    fn from(cron: Cron) -> Self {
+        Self {
+            expression: cron.expression,
+            tz: cron.tz,
+            dst_gap: cron.dst_gap,
+            dst_fold: cron.dst_fold,
+        }
+    }
+}
+
+#[typetag::serde]
+impl Trigger for Cron {
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.schedule
+            .next_after(after, self.tz.0, self.dst_gap, self.dst_fold)
+    }
+
+    fn hash(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
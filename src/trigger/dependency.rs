@@ -0,0 +1,70 @@
+use super::Trigger;
+use crate::error::JobError;
+use crate::job::RunRecord;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Which outcomes of an upstream job count as completing it.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Condition {
+    #[default]
+    Success,
+    /// The run failed, panicked or timed out. Cancelled runs don't count.
+    Failure,
+    Any,
+}
+
+impl Condition {
+    pub fn matches(&self, record: &RunRecord) -> bool {
+        match self {
+            Condition::Success => record.is_success(),
+            Condition::Failure => matches!(
+                record.outcome,
+                Err(JobError::Failed(_) | JobError::Panicked(_) | JobError::TimedOut(_))
+            ),
+            Condition::Any => true,
+        }
+    }
+}
+
+/// Fires once every one of `jobs` has completed under `condition` since the last fire. Never
+/// fires on its own schedule.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Dependency {
+    jobs: BTreeSet<String>,
+    condition: Condition,
+}
+
+impl Dependency {
+    pub fn new<'a>(jobs: impl IntoIterator<Item = &'a str>, condition: Condition) -> Self {
+        Self {
+            jobs: jobs.into_iter().map(str::to_string).collect(),
+            condition,
+        }
+    }
+
+    /// Fires after every successful run of `job`.
+    pub fn after(job: &str) -> Self {
+        Self::new([job], Condition::Success)
+    }
+}
+
+#[typetag::serde]
+impl Trigger for Dependency {
+    fn next_after(&self, _after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        None
+    }
+
+    fn upstream(&self) -> Vec<&str> {
+        self.jobs.iter().map(String::as_str).collect()
+    }
+
+    fn completes(&self, record: &RunRecord) -> bool {
+        self.jobs.contains(&record.job) && self.condition.matches(record)
+    }
+
+    fn hash(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
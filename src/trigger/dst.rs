@@ -0,0 +1,86 @@
+use chrono::{DateTime, Duration, LocalResult, NaiveDateTime, Offset, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+/// What to do with a local time that doesn't exist because clocks jump forward over it.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DstGap {
+    /// Don't fire that day.
+    Skip,
+    /// Fire as much later as the clocks jumped, e.g. at 03:30 instead of 02:30.
+    #[default]
+    Shift,
+    /// Fire at the instant the clocks jumped.
+    Transition,
+}
+
+impl DstGap {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// What to do with a local time that happens twice because clocks are turned back.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DstFold {
+    /// Fire on the first occurrence, still on summer time.
+    #[default]
+    First,
+    /// Fire on the second occurrence, after the clocks were turned back.
+    Second,
+    /// Fire on both occurrences.
+    Both,
+}
+
+impl DstFold {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+// the instants `local` stands for in `tz` under the given policies, in order
+pub(crate) fn resolve(
+    tz: chrono_tz::Tz,
+    local: NaiveDateTime,
+    gap: DstGap,
+    fold: DstFold,
+) -> impl Iterator<Item = DateTime<Utc>> {
+    let utc = |dt: DateTime<chrono_tz::Tz>| Some(dt.with_timezone(&Utc));
+    let fires = match tz.from_local_datetime(&local) {
+        LocalResult::Single(dt) => [utc(dt), None],
+        LocalResult::Ambiguous(first, second) => match fold {
+            DstFold::First => [utc(first), None],
+            DstFold::Second => [utc(second), None],
+            DstFold::Both => [utc(first), utc(second)],
+        },
+        LocalResult::None => match gap {
+            DstGap::Skip => [None, None],
+            DstGap::Shift | DstGap::Transition => [across_gap(tz, local, gap), None],
+        },
+    };
+    fires.into_iter().flatten()
+}
+
+fn across_gap(tz: chrono_tz::Tz, local: NaiveDateTime, gap: DstGap) -> Option<DateTime<Utc>> {
+    let offset_at = |utc: NaiveDateTime| tz.offset_from_utc_datetime(&utc).fix();
+    let before = offset_at(local - Duration::days(1));
+    let after = offset_at(local + Duration::days(1));
+    // read with the offset from before the jump, `local` lands as far past it as it was skipped
+    let shifted = local - Duration::seconds(before.local_minus_utc() as i64);
+    if gap == DstGap::Shift {
+        return Some(shifted.and_utc());
+    }
+
+    // the jump is at most one gap before that, find the first second on the new offset
+    let (mut low, mut high) = (
+        local - Duration::seconds(after.local_minus_utc() as i64),
+        shifted,
+    );
+    while high - low > Duration::seconds(1) {
+        let middle = low + (high - low) / 2;
+        match offset_at(middle) == after {
+            true => high = middle,
+            false => low = middle,
+        }
+    }
+    Some(high.and_utc())
+}
@@ -0,0 +1,34 @@
+use super::Trigger;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Fires whenever the event `name` is emitted through
+/// [`SchedulerHandle::emit`](crate::scheduler::SchedulerHandle::emit). Never fires on its own
+/// schedule.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Event {
+    name: String,
+}
+
+impl Event {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+        }
+    }
+}
+
+#[typetag::serde]
+impl Trigger for Event {
+    fn next_after(&self, _after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        None
+    }
+
+    fn listens_to(&self, event: &str) -> bool {
+        self.name == event
+    }
+
+    fn hash(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
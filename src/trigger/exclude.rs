@@ -0,0 +1,104 @@
+use super::calendar::{Calendar, Calendars};
+use super::Trigger;
+use crate::error::SchedulerError;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+
+// how many excluded fires in a row to skip before giving up on the inner trigger
+const MAX_SKIPS: usize = 1000;
+
+/// What to do with a fire that a calendar excludes.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExcludedRun {
+    /// Drop it.
+    #[default]
+    Skip,
+    /// Fire as soon as the calendar allows instead. Several fires excluded in a row fire once.
+    Shift,
+}
+
+impl ExcludedRun {
+    pub fn is_skip(&self) -> bool {
+        *self == Self::Skip
+    }
+}
+
+/// Fires like `inner` except when the calendar named `calendar` excludes it. The calendar comes
+/// from the scheduler running the job, see [`Scheduler::with_calendar`], or from
+/// [`Exclude::with_calendar`]; without one the trigger doesn't fire.
+///
+/// [`Scheduler::with_calendar`]: crate::scheduler::Scheduler::with_calendar
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Exclude {
+    inner: Box<dyn Trigger>,
+    calendar: String,
+    #[serde(default, skip_serializing_if = "ExcludedRun::is_skip")]
+    excluded_run: ExcludedRun,
+    // set up front, for jobs that run without a scheduler
+    #[serde(skip)]
+    inline: Option<Arc<Calendar>>,
+    // the scheduler's calendar of that name, bound whenever the job is added to one
+    #[serde(skip)]
+    bound: RwLock<Option<Arc<Calendar>>>,
+}
+
+impl Exclude {
+    pub fn new(inner: impl Trigger + 'static, calendar: &str) -> Self {
+        Self {
+            inner: Box::new(inner),
+            calendar: calendar.to_string(),
+            excluded_run: ExcludedRun::default(),
+            inline: None,
+            bound: RwLock::new(None),
+        }
+    }
+
+    pub fn with_excluded_run(mut self, excluded_run: ExcludedRun) -> Self {
+        self.excluded_run = excluded_run;
+        self
+    }
+
+    /// Uses `calendar` without a scheduler. A scheduler running the job must have the same
+    /// calendar under this name, if any.
+    pub fn with_calendar(mut self, calendar: Arc<Calendar>) -> Self {
+        self.inline = Some(calendar);
+        self
+    }
+}
+
+#[typetag::serde]
+impl Trigger for Exclude {
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let bound = self.bound.read().unwrap().clone();
+        let calendar = bound.or_else(|| self.inline.clone())?;
+        let mut after = after;
+        for _ in 0..MAX_SKIPS {
+            let next = self.inner.next_after(after)?;
+            let allowed = calendar.next_allowed(next)?;
+            if allowed == next || self.excluded_run == ExcludedRun::Shift {
+                return Some(allowed);
+            }
+            after = allowed - Duration::nanoseconds(1);
+        }
+        None
+    }
+
+    fn bind_calendars(&self, calendars: &Calendars) -> Result<(), SchedulerError> {
+        self.inner.bind_calendars(calendars)?;
+        let name = &self.calendar;
+        let calendar = match (calendars.get(name), &self.inline) {
+            (Some(named), Some(inline)) if named != inline => {
+                return Err(SchedulerError::ConflictingCalendar(name.clone()))
+            }
+            (None, None) => return Err(SchedulerError::UnknownCalendar(name.clone())),
+            (named, _) => named.cloned(),
+        };
+        *self.bound.write().unwrap() = calendar;
+        Ok(())
+    }
+
+    fn hash(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
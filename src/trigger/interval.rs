@@ -1,4 +1,4 @@
-use super::{NowUtc, Trigger};
+use super::Trigger;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
@@ -6,58 +6,103 @@ use std::time::Duration;
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Interval {
-    interval: std::time::Duration,
-    last_run: Option<DateTime<Utc>>,
+    interval: Duration,
+    // stored as `last_run`, which was never updated at runtime and so only ever anchored the
+    // interval. Unlike an anchor, a `last_run` in the future used to fire one interval after it.
+    #[serde(default, rename = "last_run", alias = "anchor")]
+    anchor: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    end: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Duration::is_zero")]
+    jitter: Duration,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    seed: u64,
+}
+
+fn is_zero(seed: &u64) -> bool {
+    *seed == 0
 }
 
 impl Interval {
-    pub fn new(interval: std::time::Duration) -> Self {
+    /// Fires every `interval`, counting from whenever it's asked for the next run unless anchored.
+    pub fn new(interval: Duration) -> Self {
         Self {
             interval,
-            last_run: None,
+            anchor: None,
+            end: None,
+            jitter: Duration::ZERO,
+            seed: 0,
         }
     }
-}
 
-#[cfg(not(test))]
-impl NowUtc for Interval {}
+    /// Fires at `anchor` and every whole interval after it, so every 15 minutes anchored at
+    /// midnight fires on the quarter hour.
+    pub fn with_anchor(mut self, anchor: DateTime<Utc>) -> Self {
+        self.anchor = Some(anchor);
+        self
+    }
 
-#[typetag::serde]
-impl Trigger for Interval {
-    fn next_runs(&self, n: usize) -> Option<Vec<DateTime<Utc>>> {
-        let now = Self::now_utc();
-        let interval_millis = self.interval.as_millis() as u64;
+    /// Doesn't fire after `end`.
+    pub fn with_end(mut self, end: DateTime<Utc>) -> Self {
+        self.end = Some(end);
+        self
+    }
 
-        let last_run = match &self.last_run {
-            Some(last_run_inner) => {
-                let time_passed = now - last_run_inner;
-                let intervals_passed: u64 = (time_passed.to_std().unwrap().as_millis()
-                    / self.interval.as_millis())
-                .try_into()
-                .unwrap();
-                *last_run_inner + Duration::from_millis((intervals_passed) * interval_millis)
-            }
-            None => now,
-        };
+    /// Delays every run by up to `jitter`, capped at the interval. The delay looks random but is
+    /// derived from the run's slot and a seed picked here, so previews match the actual runs.
+    /// Unanchored intervals count from the Unix epoch once jittered, as a jittered run doesn't
+    /// tell where its slot was.
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self.seed = rand::random();
+        self
+    }
 
-        Some(
-            (1..n + 1)
-                .map(move |i| last_run + Duration::from_millis(i as u64 * interval_millis))
-                .collect(),
-        )
+    // a pseudo-random delay for the run due at `due`, stable across calls
+    fn jitter(&self, due: DateTime<Utc>) -> chrono::Duration {
+        let max = self.jitter.min(self.interval).as_millis() as u64;
+        if max == 0 {
+            return chrono::Duration::zero();
+        }
+        // splitmix64
+        let mut x = (self.seed ^ due.timestamp_millis() as u64).wrapping_add(0x9e3779b97f4a7c15);
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+        x ^= x >> 31;
+        chrono::Duration::milliseconds((x % max) as i64)
     }
+}
 
-    fn time_to_next_runs(&self, n: usize) -> Option<Vec<Duration>> {
-        let next_runs = self.next_runs(n)?;
-        Some(
-            next_runs
-                .into_iter()
-                .map(move |dt| {
-                    let now = Utc::now();
-                    (dt - now).to_std().unwrap()
-                })
-                .collect(),
-        )
+#[typetag::serde]
+impl Trigger for Interval {
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let interval = chrono::Duration::from_std(self.interval).ok()?;
+        let interval_millis = interval.num_milliseconds();
+        if interval_millis <= 0 {
+            return None;
+        }
+        let anchor = match self.jitter.is_zero() {
+            true => self.anchor,
+            false => self.anchor.or(Some(DateTime::UNIX_EPOCH)),
+        };
+        let slot = match anchor {
+            Some(anchor) if anchor <= after => {
+                let intervals_passed = (after - anchor).num_milliseconds() / interval_millis;
+                let previous =
+                    anchor + chrono::Duration::milliseconds(intervals_passed * interval_millis);
+                // the previous run may still be pending behind its jitter
+                match previous + self.jitter(previous) > after {
+                    true => previous,
+                    false => previous + interval,
+                }
+            }
+            Some(anchor) => anchor,
+            None => after + interval,
+        };
+        if self.end.is_some_and(|end| slot > end) {
+            return None;
+        }
+        Some(slot + self.jitter(slot))
     }
 
     fn hash(&self) -> String {
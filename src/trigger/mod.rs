@@ -1,7 +1,20 @@
+pub mod bounded;
+pub mod calendar;
+pub mod cron;
+pub mod dependency;
+pub mod dst;
+pub mod event;
+pub mod exclude;
 pub mod interval;
+pub mod monthly;
 pub mod oneshot;
 pub mod trigger_set;
 pub mod weekly;
+pub mod yearly;
+
+use crate::clock::Clock;
+use crate::error::SchedulerError;
+use crate::job::RunRecord;
 
 use chrono::{DateTime, Utc};
 use std::time::Duration;
@@ -9,19 +22,70 @@ use std::time::Duration;
 #[typetag::serde(tag = "type")]
 pub trait Trigger: std::fmt::Debug
 where
-    Self: Send,
+    Self: Send + Sync,
 {
-    fn next_runs(&self, _n: usize) -> Option<Vec<DateTime<Utc>>> {
-        None
+    /// First fire strictly after `after`, or `None` if the trigger is done.
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>>;
+
+    /// Lazily iterates over the fires strictly after `from`.
+    fn occurrences(&self, from: DateTime<Utc>) -> Occurrences<'_> {
+        let mut after = Some(from);
+        Box::new(std::iter::from_fn(move || {
+            after = self.next_after(after?);
+            after
+        }))
+    }
+
+    /// The next `n` fires after `clock`'s current time. Kept for compatibility, prefer
+    /// [`Trigger::next_after`] and [`Trigger::occurrences`].
+    fn next_runs(&self, clock: &dyn Clock, n: usize) -> Option<Vec<DateTime<Utc>>> {
+        let next_runs: Vec<DateTime<Utc>> = self.occurrences(clock.now()).take(n).collect();
+
+        match next_runs.is_empty() {
+            true => None,
+            false => Some(next_runs),
+        }
+    }
+
+    /// Like [`Trigger::next_runs`], as durations from `clock`'s current time.
+    fn time_to_next_runs(&self, clock: &dyn Clock, n: usize) -> Option<Vec<Duration>> {
+        let now = clock.now();
+        let next_runs = self.next_runs(clock, n)?;
+        Some(
+            next_runs
+                .into_iter()
+                .map(|dt| (dt - now).to_std().unwrap_or_default())
+                .collect(),
+        )
+    }
+
+    /// Jobs this trigger waits for. It fires once all of them have completed a run accepted by
+    /// [`Trigger::completes`] since it last fired.
+    fn upstream(&self) -> Vec<&str> {
+        Vec::new()
     }
 
-    fn time_to_next_runs(&self, _n: usize) -> Option<Vec<Duration>> {
-        None
+    /// Whether `record` counts as a completed run of one of the [`Trigger::upstream`] jobs.
+    fn completes(&self, _record: &RunRecord) -> bool {
+        false
+    }
+
+    /// Whether emitting `event` through the scheduler's handle fires this trigger.
+    fn listens_to(&self, _event: &str) -> bool {
+        false
+    }
+
+    /// Hands the trigger the calendars it refers to by name, failing if one is missing or
+    /// clashes with a calendar the trigger already has.
+    fn bind_calendars(&self, _calendars: &Calendars) -> Result<(), SchedulerError> {
+        Ok(())
     }
 
     fn hash(&self) -> String;
 }
 
+pub type Occurrences<'a> = Box<dyn Iterator<Item = DateTime<Utc>> + Send + 'a>;
+
 impl PartialEq for dyn Trigger {
     fn eq(&self, other: &Self) -> bool {
         self.hash() == other.hash()
@@ -32,7 +96,7 @@ impl Eq for dyn Trigger {}
 
 impl PartialOrd for dyn Trigger {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.hash().cmp(&other.hash()))
+        Some(self.cmp(other))
     }
 }
 
@@ -42,10 +106,18 @@ impl Ord for dyn Trigger {
     }
 }
 
-pub trait NowUtc {
-    fn now_utc() -> DateTime<Utc> {
-        Utc::now()
-    }
-}
-
-pub use self::{interval::Interval, oneshot::Oneshot, trigger_set::TriggerSet, weekly::Weekly};
+pub use self::{
+    bounded::{Between, Limit, Until},
+    calendar::{Calendar, Calendars, Exclusion},
+    cron::Cron,
+    dependency::{Condition, Dependency},
+    dst::{DstFold, DstGap},
+    event::Event,
+    exclude::{Exclude, ExcludedRun},
+    interval::Interval,
+    monthly::{MissingDay, MonthDay, MonthDayError, Monthly},
+    oneshot::Oneshot,
+    trigger_set::TriggerSet,
+    weekly::{TimeOfDayError, Weekly},
+    yearly::Yearly,
+};
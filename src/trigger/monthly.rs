@@ -0,0 +1,200 @@
+use super::dst::{DstFold, DstGap};
+use super::weekly::Tz;
+use super::Trigger;
+use chrono::{
+    DateTime, Datelike, Days, Duration as ChronoDuration, Months, NaiveDate, Utc, Weekday,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+/// The day of the month a [`Monthly`] or [`Yearly`](super::Yearly) trigger fires on.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MonthDay {
+    /// The given day of the month, starting at 1.
+    Day(u32),
+    /// The last day of the month.
+    Last,
+    /// The last Monday to Friday of the month.
+    LastBusinessDay,
+    /// The nth given weekday of the month, starting at 1, e.g. the first Monday.
+    NthWeekday(u32, Weekday),
+    /// The last given weekday of the month.
+    LastWeekday(Weekday),
+}
+
+/// What to do in months without the requested day, like the 31st in April or a fifth Friday.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MissingDay {
+    /// Don't fire that month.
+    #[default]
+    Skip,
+    /// Fire on the last day of the month, or the last of the requested weekday.
+    Clamp,
+}
+
+impl MissingDay {
+    pub fn is_skip(&self) -> bool {
+        *self == Self::Skip
+    }
+}
+
+/// A month or day of the month that a [`Monthly`] or [`Yearly`](super::Yearly) trigger can
+/// never fire on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MonthDayError {
+    /// A month outside 1 to 12.
+    Month(u32),
+    /// A day no month has, or that the month has in no year.
+    Day(MonthDay),
+}
+
+impl fmt::Display for MonthDayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MonthDayError::Month(month) => write!(f, "month {} is not between 1 and 12", month),
+            MonthDayError::Day(day) => write!(f, "{:?} is not a day of the month", day),
+        }
+    }
+}
+
+impl std::error::Error for MonthDayError {}
+
+impl MonthDay {
+    // whether the day can come up in `month`, or in any month
+    pub(crate) fn validate(&self, month: Option<u32>) -> Result<(), MonthDayError> {
+        let days = match month {
+            Some(2) => 29,
+            Some(4 | 6 | 9 | 11) => 30,
+            _ => 31,
+        };
+        match *self {
+            MonthDay::Day(day) if !(1..=days).contains(&day) => Err(MonthDayError::Day(*self)),
+            MonthDay::NthWeekday(n, _) if !(1..=5).contains(&n) => Err(MonthDayError::Day(*self)),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn in_month(
+        &self,
+        year: i32,
+        month: u32,
+        missing_day: MissingDay,
+    ) -> Option<NaiveDate> {
+        let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+        let last = first.checked_add_months(Months::new(1))?.pred_opt()?;
+        let clamp = missing_day == MissingDay::Clamp;
+        let last_matching = |matches: &dyn Fn(NaiveDate) -> bool| {
+            last.iter_days().rev().take(7).find(|date| matches(*date))
+        };
+        match *self {
+            MonthDay::Day(day) if day <= last.day() => first.with_day(day),
+            MonthDay::Day(_) => clamp.then_some(last),
+            MonthDay::Last => Some(last),
+            MonthDay::LastBusinessDay => {
+                last_matching(&|date| date.weekday().num_days_from_monday() < 5)
+            }
+            MonthDay::NthWeekday(n, weekday) => {
+                let offset = (7 + weekday.num_days_from_monday()
+                    - first.weekday().num_days_from_monday())
+                    % 7;
+                let nth = first.checked_add_days(Days::new((offset + 7 * (n - 1)) as u64))?;
+                match nth <= last {
+                    true => Some(nth),
+                    false if clamp => last_matching(&|date| date.weekday() == weekday),
+                    false => None,
+                }
+            }
+            MonthDay::LastWeekday(weekday) => last_matching(&|date| date.weekday() == weekday),
+        }
+    }
+}
+
+/// Fires once a month at `time` past local midnight of the given day.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(try_from = "MonthlyFields")]
+pub struct Monthly {
+    day: MonthDay,
+    time: Duration,
+    tz: Tz,
+    #[serde(default, skip_serializing_if = "MissingDay::is_skip")]
+    missing_day: MissingDay,
+    #[serde(default, skip_serializing_if = "DstGap::is_default")]
+    dst_gap: DstGap,
+    #[serde(default, skip_serializing_if = "DstFold::is_default")]
+    dst_fold: DstFold,
+}
+
+impl Monthly {
+    pub fn new(day: MonthDay, time: Duration, tz: chrono_tz::Tz) -> Result<Self, MonthDayError> {
+        day.validate(None)?;
+        Ok(Self {
+            day,
+            time,
+            tz: Tz(tz),
+            missing_day: MissingDay::default(),
+            dst_gap: DstGap::default(),
+            dst_fold: DstFold::default(),
+        })
+    }
+
+    pub fn with_missing_day(mut self, missing_day: MissingDay) -> Self {
+        self.missing_day = missing_day;
+        self
+    }
+
+    pub fn with_dst_gap(mut self, dst_gap: DstGap) -> Self {
+        self.dst_gap = dst_gap;
+        self
+    }
+
+    pub fn with_dst_fold(mut self, dst_fold: DstFold) -> Self {
+        self.dst_fold = dst_fold;
+        self
+    }
+}
+
+#[typetag::serde]
+impl Trigger for Monthly {
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let time = ChronoDuration::from_std(self.time).ok()?;
+        let month = after.with_timezone(&self.tz.0).date_naive().with_day(1)?;
+        // four years, so even a day that is rarely there or falls into a DST gap is found
+        (0..48)
+            .filter_map(|months| month.checked_add_months(Months::new(months)))
+            .filter_map(|month| {
+                self.day
+                    .in_month(month.year(), month.month(), self.missing_day)
+            })
+            .flat_map(|date| self.tz.at(date, time, self.dst_gap, self.dst_fold))
+            .find(|dt| *dt > after)
+    }
+
+    fn hash(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+#[derive(Deserialize)]
+struct MonthlyFields {
+    day: MonthDay,
+    time: Duration,
+    tz: Tz,
+    #[serde(default)]
+    missing_day: MissingDay,
+    #[serde(default)]
+    dst_gap: DstGap,
+    #[serde(default)]
+    dst_fold: DstFold,
+}
+
+impl TryFrom<MonthlyFields> for Monthly {
+    type Error = MonthDayError;
+
+    fn try_from(fields: MonthlyFields) -> Result<Self, Self::Error> {
+        Ok(Self::new(fields.day, fields.time, fields.tz.0)?
+            .with_missing_day(fields.missing_day)
+            .with_dst_gap(fields.dst_gap)
+            .with_dst_fold(fields.dst_fold))
+    }
+}
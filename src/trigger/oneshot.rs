@@ -1,7 +1,6 @@
-use super::{NowUtc, Trigger};
+use super::Trigger;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Oneshot {
@@ -14,29 +13,10 @@ impl Oneshot {
     }
 }
 
-#[cfg(not(test))]
-impl NowUtc for Oneshot {}
-
 #[typetag::serde]
 impl Trigger for Oneshot {
-    fn next_runs(&self, _n: usize) -> Option<Vec<DateTime<Utc>>> {
-        match self.datetime >= Self::now_utc() {
-            true => Some(vec![self.datetime]),
-            false => None,
-        }
-    }
-
-    fn time_to_next_runs(&self, n: usize) -> Option<Vec<Duration>> {
-        let next_runs = self.next_runs(n)?;
-        Some(
-            next_runs
-                .into_iter()
-                .map(move |dt| {
-                    let now = Self::now_utc();
-                    (dt.with_timezone(&Utc) - now).to_std().unwrap()
-                })
-                .collect(),
-        )
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        (self.datetime > after).then_some(self.datetime)
     }
 
     fn hash(&self) -> String {
@@ -1,5 +1,7 @@
 use crate::trigger::Trigger;
 
+use chrono::{DateTime, Duration, Utc};
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
 use std::fmt::Debug;
@@ -11,6 +13,36 @@ impl TriggerSet {
     pub fn iter(&self) -> std::collections::btree_set::Iter<'_, Box<dyn Trigger>> {
         self.0.iter()
     }
+
+    /// First fire of any of the triggers strictly after `after`.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.next_fire_after(after).map(|(_, at)| at)
+    }
+
+    /// Like [`TriggerSet::next_after`], along with the trigger that fires.
+    pub fn next_fire_after(&self, after: DateTime<Utc>) -> Option<(&dyn Trigger, DateTime<Utc>)> {
+        self.iter()
+            .filter_map(|trigger| Some((trigger.as_ref(), trigger.next_after(after)?)))
+            .min_by_key(|(_, at)| *at)
+    }
+
+    /// Fires of all triggers in `[from, until)`, in order, with the trigger behind each of them.
+    pub fn preview(
+        &self,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Vec<(&dyn Trigger, DateTime<Utc>)> {
+        self.iter()
+            .map(|trigger| {
+                let trigger = trigger.as_ref();
+                trigger
+                    .occurrences(from - Duration::nanoseconds(1))
+                    .take_while(move |at| *at < until)
+                    .map(move |at| (trigger, at))
+            })
+            .kmerge_by(|a, b| a.1 < b.1)
+            .collect()
+    }
 }
 
 impl Debug for TriggerSet {
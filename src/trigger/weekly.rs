@@ -1,15 +1,16 @@
-use super::{NowUtc, Trigger};
-use chrono::{DateTime, Datelike, Duration as ChronoDuration, DurationRound, Utc};
+use super::dst::{self, DstFold, DstGap};
+use super::Trigger;
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, NaiveDate, NaiveTime, Utc, Weekday};
 use serde::{Deserialize, Serialize};
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
 use std::time::Duration;
 
 #[derive(Clone, Serialize, Deserialize, Hash, Eq, PartialEq)]
-pub struct Tz(chrono_tz::Tz);
+pub struct Tz(pub(crate) chrono_tz::Tz);
 
 impl PartialOrd for Tz {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.0.name().partial_cmp(other.0.name())
+        Some(self.cmp(other))
     }
 }
 
@@ -19,86 +20,172 @@ impl Ord for Tz {
     }
 }
 
+impl Tz {
+    // `time` past local midnight of `date`, resolving DST changes under the given policies
+    pub(crate) fn at(
+        &self,
+        date: NaiveDate,
+        time: ChronoDuration,
+        gap: DstGap,
+        fold: DstFold,
+    ) -> impl Iterator<Item = DateTime<Utc>> {
+        dst::resolve(self.0, date.and_time(NaiveTime::MIN) + time, gap, fold)
+    }
+}
+
 impl Debug for Tz {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(self.0.name())
     }
 }
 
+/// A time of day that is not under 24 hours.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeOfDayError {
+    weekday: Weekday,
+    time: Duration,
+}
+
+impl fmt::Display for TimeOfDayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "time {:?} on {} is not under 24 hours",
+            self.time, self.weekday
+        )
+    }
+}
+
+impl std::error::Error for TimeOfDayError {}
+
+/// Fires at given times of day on given weekdays, e.g. Monday to Friday at 08:00 and 17:30.
 #[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(into = "WeeklyRepr", try_from = "WeeklyRepr")]
 pub struct Weekly {
-    weekdays: [bool; 7],
-    time: Duration,
+    // sorted times of day for each weekday, starting on Monday
+    times: [Vec<Duration>; 7],
     tz: Tz,
+    dst_gap: DstGap,
+    dst_fold: DstFold,
 }
 
 impl Weekly {
+    /// Fires at `time` on each weekday enabled in `weekdays`, starting on Monday.
     pub fn new(weekdays: [bool; 7], time: Duration, tz: chrono_tz::Tz) -> Self {
         Self {
-            weekdays,
-            time,
+            times: weekdays.map(|enabled| if enabled { vec![time] } else { vec![] }),
             tz: Tz(tz),
+            dst_gap: DstGap::default(),
+            dst_fold: DstFold::default(),
         }
     }
-}
 
-#[cfg(not(test))]
-impl NowUtc for Weekly {}
+    /// Fires at each of the times of day listed for a weekday, starting on Monday.
+    pub fn from_times(
+        times: [Vec<Duration>; 7],
+        tz: chrono_tz::Tz,
+    ) -> Result<Self, TimeOfDayError> {
+        let mut weekday = Weekday::Mon;
+        let mut sorted = times;
+        for times in sorted.iter_mut() {
+            if let Some(&time) = times.iter().find(|time| time.as_secs() >= 24 * 3600) {
+                return Err(TimeOfDayError { weekday, time });
+            }
+            times.sort();
+            times.dedup();
+            weekday = weekday.succ();
+        }
+        Ok(Self {
+            times: sorted,
+            ..Self::new([false; 7], Duration::ZERO, tz)
+        })
+    }
+
+    pub fn with_dst_gap(mut self, dst_gap: DstGap) -> Self {
+        self.dst_gap = dst_gap;
+        self
+    }
+
+    pub fn with_dst_fold(mut self, dst_fold: DstFold) -> Self {
+        self.dst_fold = dst_fold;
+        self
+    }
+
+    pub fn times(&self, weekday: Weekday) -> &[Duration] {
+        &self.times[weekday.num_days_from_monday() as usize]
+    }
+}
 
 #[typetag::serde]
 impl Trigger for Weekly {
-    fn next_runs(&self, n: usize) -> Option<Vec<DateTime<Utc>>> {
-        match self.weekdays.iter().all(|e| !e) {
-            true => None,
-            false => Some(
-                self.weekdays
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let local_date = after.with_timezone(&self.tz.0).date_naive();
+        // two weeks, so a weekday whose time falls into a DST gap still finds next week's run
+        (0..=14)
+            .map(|days| local_date + ChronoDuration::days(days))
+            .find_map(|date| {
+                // DST policies can move a time past a later one, so take the earliest of the day
+                self.times(date.weekday())
                     .iter()
-                    .cycle()
-                    .enumerate()
-                    .skip(Self::now_utc().weekday().num_days_from_monday() as usize)
-                    .filter_map(move |(i, e)| {
-                        let now = Self::now_utc().with_timezone(&self.tz.0);
-                        let weekday = now.weekday();
-                        let weekday_offset = weekday.num_days_from_monday() as i64;
-                        let now_midnight = now
-                            .duration_round(ChronoDuration::days(1))
-                            .unwrap()
-                            .naive_local();
-                        let next_dt_naive = (now_midnight
-                            + ChronoDuration::days(i as i64 - weekday_offset)
-                            + self.time)
-                            .and_local_timezone(self.tz.0);
-                        match next_dt_naive {
-                            chrono::LocalResult::None => None,
-                            chrono::LocalResult::Ambiguous(_, _) => None,
-                            chrono::LocalResult::Single(res) => Some((*e, res)),
-                        }
-                    })
-                    .skip_while(move |(_e, dt)| *dt < Self::now_utc())
-                    .filter_map(|(e, dt)| match e {
-                        true => Some(dt.with_timezone(&Utc)),
-                        false => None,
-                    })
-                    .take(n)
-                    .collect(),
-            ),
-        }
-    }
-
-    fn time_to_next_runs(&self, n: usize) -> Option<Vec<Duration>> {
-        let next_runs = self.next_runs(n)?;
-        Some(
-            next_runs
-                .into_iter()
-                .map(move |dt| {
-                    let now = Self::now_utc();
-                    (dt - now).to_std().unwrap()
-                })
-                .collect(),
-        )
+                    .filter_map(|time| ChronoDuration::from_std(*time).ok())
+                    .flat_map(|time| self.tz.at(date, time, self.dst_gap, self.dst_fold))
+                    .filter(|dt| *dt > after)
+                    .min()
+            })
     }
 
     fn hash(&self) -> String {
         serde_json::to_string(self).unwrap()
     }
 }
+
+// a single time shared by all enabled weekdays keeps the original `weekdays` and `time` fields
+#[derive(Serialize, Deserialize)]
+struct WeeklyRepr {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    weekdays: Option<[bool; 7]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    time: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    times: Option<[Vec<Duration>; 7]>,
+    tz: Tz,
+    #[serde(default, skip_serializing_if = "DstGap::is_default")]
+    dst_gap: DstGap,
+    #[serde(default, skip_serializing_if = "DstFold::is_default")]
+    dst_fold: DstFold,
+}
+
+impl From<Weekly> for WeeklyRepr {
+    fn from(weekly: Weekly) -> Self {
+        let mut shared = weekly.times.iter().filter(|times| !times.is_empty());
+        let time = match shared.next() {
+            Some(first) if first.len() == 1 && shared.all(|times| times == first) => Some(first[0]),
+            _ => None,
+        };
+        Self {
+            weekdays: time.map(|_| weekly.times.each_ref().map(|times| !times.is_empty())),
+            time,
+            times: time.is_none().then_some(weekly.times),
+            tz: weekly.tz,
+            dst_gap: weekly.dst_gap,
+            dst_fold: weekly.dst_fold,
+        }
+    }
+}
+
+impl TryFrom<WeeklyRepr> for Weekly {
+    type Error = String;
+
+    fn try_from(repr: WeeklyRepr) -> Result<Self, Self::Error> {
+        let weekly = match (repr.times, repr.weekdays, repr.time) {
+            (Some(times), None, None) => {
+                Weekly::from_times(times, repr.tz.0).map_err(|e| e.to_string())?
+            }
+            (None, Some(weekdays), Some(time)) => Weekly::new(weekdays, time, repr.tz.0),
+            _ => return Err("expected either `times` or `weekdays` and `time`".to_string()),
+        };
+        Ok(weekly
+            .with_dst_gap(repr.dst_gap)
+            .with_dst_fold(repr.dst_fold))
+    }
+}
@@ -0,0 +1,105 @@
+use super::dst::{DstFold, DstGap};
+use super::monthly::{MissingDay, MonthDay, MonthDayError};
+use super::weekly::Tz;
+use super::Trigger;
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Fires once a year at `time` past local midnight of the given day of `month`, 1 to 12.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(try_from = "YearlyFields")]
+pub struct Yearly {
+    month: u32,
+    day: MonthDay,
+    time: Duration,
+    tz: Tz,
+    #[serde(default, skip_serializing_if = "MissingDay::is_skip")]
+    missing_day: MissingDay,
+    #[serde(default, skip_serializing_if = "DstGap::is_default")]
+    dst_gap: DstGap,
+    #[serde(default, skip_serializing_if = "DstFold::is_default")]
+    dst_fold: DstFold,
+}
+
+impl Yearly {
+    pub fn new(
+        month: u32,
+        day: MonthDay,
+        time: Duration,
+        tz: chrono_tz::Tz,
+    ) -> Result<Self, MonthDayError> {
+        if !(1..=12).contains(&month) {
+            return Err(MonthDayError::Month(month));
+        }
+        day.validate(Some(month))?;
+        Ok(Self {
+            month,
+            day,
+            time,
+            tz: Tz(tz),
+            missing_day: MissingDay::default(),
+            dst_gap: DstGap::default(),
+            dst_fold: DstFold::default(),
+        })
+    }
+
+    pub fn with_missing_day(mut self, missing_day: MissingDay) -> Self {
+        self.missing_day = missing_day;
+        self
+    }
+
+    pub fn with_dst_gap(mut self, dst_gap: DstGap) -> Self {
+        self.dst_gap = dst_gap;
+        self
+    }
+
+    pub fn with_dst_fold(mut self, dst_fold: DstFold) -> Self {
+        self.dst_fold = dst_fold;
+        self
+    }
+}
+
+#[typetag::serde]
+impl Trigger for Yearly {
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let time = ChronoDuration::from_std(self.time).ok()?;
+        let year = after.with_timezone(&self.tz.0).year();
+        // February 29th can be eight years apart
+        (year..=year + 8)
+            .filter_map(|year| self.day.in_month(year, self.month, self.missing_day))
+            .flat_map(|date| self.tz.at(date, time, self.dst_gap, self.dst_fold))
+            .find(|dt| *dt > after)
+    }
+
+    fn hash(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+#[derive(Deserialize)]
+struct YearlyFields {
+    month: u32,
+    day: MonthDay,
+    time: Duration,
+    tz: Tz,
+    #[serde(default)]
+    missing_day: MissingDay,
+    #[serde(default)]
+    dst_gap: DstGap,
+    #[serde(default)]
+    dst_fold: DstFold,
+}
+
+impl TryFrom<YearlyFields> for Yearly {
+    type Error = MonthDayError;
+
+    fn try_from(fields: YearlyFields) -> Result<Self, Self::Error> {
+        Ok(
+            Self::new(fields.month, fields.day, fields.time, fields.tz.0)?
+                .with_missing_day(fields.missing_day)
+                .with_dst_gap(fields.dst_gap)
+                .with_dst_fold(fields.dst_fold),
+        )
+    }
+}